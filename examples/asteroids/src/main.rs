@@ -34,10 +34,7 @@ impl Moon{
     fn draw(&mut self, g: &mut impl Graphics) {
         let (r1, r2) = (self.image.width()/2., self.image.height()/2.);
         g.draw_image_at(
-            Some(Transform {
-                rotate: self.rotation,
-                translate: (190., 190.)
-            }),
+            Some(Matrix2D::translation(190., 190.).rotate(self.rotation)),
             &self.image,
             -r1,
             -r2,
@@ -127,7 +124,7 @@ impl State for Game {
         }
     }
 
-    fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window) {
+    fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {
         g.fill_rect(&[0, 0, 0, 255], 0., 0., 300., 300.);
         if let Some(asteroid) = &mut self.asteroid {
             asteroid.draw(g);
@@ -138,7 +135,7 @@ impl State for Game {
         }
     }
 
-    fn update(&mut self, _window: &mut impl Window) {
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {
         if let Some(asteroid) = &mut self.asteroid {
             asteroid.update();
             self.moon.as_mut().unwrap().update();
@@ -154,7 +151,7 @@ fn main() {
         Settings {
             show_ups_fps: true,
             background_color: Some([255, 255, 255, 255]),
-            draw_center: true,
+            scale_mode: ScaleMode::Fit,
             ..Default::default()
         },
     );
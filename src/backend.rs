@@ -0,0 +1,148 @@
+use super::{AudioType, Graphics, Settings, State, Window};
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+/// 平台后端
+///
+/// 内置的后端有D2DBackend(Windows)、LinuxBackend、BrowserBackend(Web)和HeadlessBackend，
+/// 第三方后端实现这个trait之后可以通过run_with启动
+///
+/// # Example
+///
+/// ```ignore
+/// mengine::run_with::<MyBackend, Game>("游戏", 300., 300., Settings::default());
+/// ```
+pub trait Backend: 'static {
+    /// 后端的原生图片，通过Image::new包装后交给State
+    type Image: NativeImage;
+    /// 后端的原生声音数据，通过Sound::new包装后交给State
+    type Sound: Any;
+    type Window: Window;
+    type Graphics: Graphics;
+
+    /// 创建窗口并运行游戏
+    fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings);
+
+    /// 播放声音
+    fn play_sound(sound: &Self::Sound);
+}
+
+/// 后端的原生图片
+pub trait NativeImage: Any {
+    fn width(&self) -> f64;
+    fn height(&self) -> f64;
+}
+
+/// 图片，保存后端创建的原生图片
+#[derive(Clone)]
+pub struct Image {
+    native: Rc<dyn NativeImage>,
+}
+
+impl Image {
+    pub fn new<T: NativeImage>(native: T) -> Image {
+        Image {
+            native: Rc::new(native),
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.native.width()
+    }
+    pub fn height(&self) -> f64 {
+        self.native.height()
+    }
+
+    /// 取出原生图片，类型不匹配时返回None
+    pub fn native<T: NativeImage>(&self) -> Option<&T> {
+        let native: &dyn Any = &*self.native;
+        native.downcast_ref::<T>()
+    }
+}
+
+impl fmt::Debug for Image {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .finish()
+    }
+}
+
+/// 声音，保存后端创建的原生声音数据
+#[derive(Clone)]
+pub struct Sound {
+    audio_type: AudioType,
+    native: Rc<dyn Any>,
+}
+
+impl Sound {
+    pub fn new<T: Any>(audio_type: AudioType, native: T) -> Sound {
+        Sound {
+            audio_type,
+            native: Rc::new(native),
+        }
+    }
+
+    pub fn audio_type(&self) -> &AudioType {
+        &self.audio_type
+    }
+
+    /// 取出原生声音数据，类型不匹配时返回None
+    pub fn native<T: Any>(&self) -> Option<&T> {
+        self.native.downcast_ref::<T>()
+    }
+}
+
+impl fmt::Debug for Sound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sound")
+            .field("audio_type", &self.audio_type)
+            .finish()
+    }
+}
+
+/// 当前平台默认使用的后端
+#[cfg(all(windows, not(feature = "headless")))]
+pub type DefaultBackend = super::windows::D2DBackend;
+/// 当前平台默认使用的后端
+#[cfg(any(target_arch = "asmjs", target_arch = "wasm32"))]
+pub type DefaultBackend = super::web::BrowserBackend;
+/// 当前平台默认使用的后端
+#[cfg(all(target_os = "linux", not(feature = "headless")))]
+pub type DefaultBackend = super::linux::LinuxBackend;
+/// 当前平台默认使用的后端
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(any(windows, target_os = "linux")))
+))]
+pub type DefaultBackend = super::headless::HeadlessBackend;
+
+thread_local! {
+    /// 正在运行的后端的play_sound
+    static SOUND_PLAYER: Cell<fn(&Sound)> = const { Cell::new(play_with::<DefaultBackend>) };
+}
+
+fn play_with<B: Backend>(sound: &Sound) {
+    if let Some(native) = sound.native::<B::Sound>() {
+        B::play_sound(native);
+    }
+}
+
+/// 使用指定的后端运行游戏
+pub fn run_with<B: Backend, S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+    SOUND_PLAYER.with(|player| player.set(play_with::<B>));
+    B::run::<S>(title, width, height, settings);
+}
+
+/// 使用默认后端运行游戏
+pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+    run_with::<DefaultBackend, S>(title, width, height, settings);
+}
+
+/// 使用正在运行的后端播放声音
+pub fn play_sound(sound: &Sound) {
+    SOUND_PLAYER.with(|player| player.get())(sound);
+}
@@ -0,0 +1,102 @@
+use super::{Image, Matrix2D};
+
+/// 渐变的颜色节点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// 在渐变中的位置(0~1)
+    pub offset: f64,
+    pub color: [u8; 4],
+}
+
+/// 填充图形的画刷，坐标和图形一样受当前变换影响
+///
+/// # Example
+///
+/// ```ignore
+/// //从上到下的天空背景
+/// let sky = Brush::linear_gradient(0., 0., 0., 300.)
+///     .with_stop(0., [40, 80, 200, 255])
+///     .with_stop(1., [200, 220, 255, 255]);
+/// g.fill_rect_with(&sky, 0., 0., 400., 300.);
+///
+/// //四周变暗的晕影
+/// let vignette = Brush::radial_gradient(200., 150., 250.)
+///     .with_stop(0.6, [0, 0, 0, 0])
+///     .with_stop(1., [0, 0, 0, 200]);
+/// g.fill_rect_with(&vignette, 0., 0., 400., 300.);
+/// ```
+#[derive(Debug, Clone)]
+pub enum Brush {
+    /// 纯色
+    Solid([u8; 4]),
+    /// 从start到end的线性渐变，start和end重合时不绘制
+    LinearGradient {
+        start: [f64; 2],
+        end: [f64; 2],
+        stops: Vec<GradientStop>,
+    },
+    /// 从圆心(offset为0)到圆周(offset为1)的径向渐变
+    RadialGradient {
+        center: [f64; 2],
+        radius: f64,
+        stops: Vec<GradientStop>,
+    },
+    /// 平铺的图片，transform为图片坐标到游戏坐标的变换
+    Pattern { image: Image, transform: Matrix2D },
+}
+
+impl Brush {
+    /// 从(x0, y0)到(x1, y1)的线性渐变，使用with_stop添加颜色
+    pub fn linear_gradient(x0: f64, y0: f64, x1: f64, y1: f64) -> Brush {
+        Brush::LinearGradient {
+            start: [x0, y0],
+            end: [x1, y1],
+            stops: vec![],
+        }
+    }
+
+    /// 圆心为(x, y)的径向渐变，使用with_stop添加颜色
+    pub fn radial_gradient(x: f64, y: f64, radius: f64) -> Brush {
+        Brush::RadialGradient {
+            center: [x, y],
+            radius,
+            stops: vec![],
+        }
+    }
+
+    /// 图片从坐标原点开始平铺
+    pub fn pattern(image: Image) -> Brush {
+        Brush::Pattern {
+            image,
+            transform: Matrix2D::IDENTITY,
+        }
+    }
+
+    /// 添加渐变的颜色节点，位置相同时按添加的顺序排列，不是渐变时忽略
+    pub fn with_stop(mut self, offset: f64, color: [u8; 4]) -> Brush {
+        if let Brush::LinearGradient { stops, .. } | Brush::RadialGradient { stops, .. } = &mut self
+        {
+            let offset = offset.clamp(0.0, 1.0);
+            let index = stops
+                .iter()
+                .take_while(|stop| stop.offset <= offset)
+                .count();
+            stops.insert(index, GradientStop { offset, color });
+        }
+        self
+    }
+
+    /// 设置图案的变换，不是图案时忽略
+    pub fn with_transform(mut self, matrix: Matrix2D) -> Brush {
+        if let Brush::Pattern { transform, .. } = &mut self {
+            *transform = matrix;
+        }
+        self
+    }
+}
+
+impl From<[u8; 4]> for Brush {
+    fn from(color: [u8; 4]) -> Brush {
+        Brush::Solid(color)
+    }
+}
@@ -0,0 +1,164 @@
+use super::{
+    Brush, DrawOptions, FillRule, Graphics, Image, ImageData, Matrix2D, Path, Sampling, StrokeStyle,
+};
+use std::fmt;
+
+/// 后端的离屏画布
+pub trait NativeCanvas: Graphics + 'static {
+    /// 用color填充整个画布(不受变换和裁剪影响)，并清空save保存的状态和裁剪区域
+    fn clear(&mut self, color: &[u8; 4]);
+    /// 将画布当前的内容复制为图片
+    fn to_image(&self) -> Image;
+}
+
+/// 离屏画布，通过Window::create_canvas创建，画布大小为像素
+///
+/// 画布的内容可以转换为Image，用于预先绘制静态的地图、组合小地图、缓存文字或者实现后期效果
+///
+/// # Example
+///
+/// ```ignore
+/// let mut canvas = window.create_canvas(200., 150.);
+/// for tile in &tiles {
+///     canvas.draw_image_at(None, &tile.image, tile.x, tile.y);
+/// }
+/// let level = canvas.to_image();
+/// //每一帧只需要绘制一次图片
+/// g.draw_image(None, &level, Some([camera_x, camera_y, 100., 100.]), Some([0., 0., 100., 100.]));
+/// ```
+pub struct Canvas {
+    width: f64,
+    height: f64,
+    native: Box<dyn NativeCanvas>,
+}
+
+impl Canvas {
+    pub fn new<T: NativeCanvas>(width: f64, height: f64, native: T) -> Canvas {
+        Canvas {
+            width,
+            height,
+            native: Box::new(native),
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// 用color填充整个画布，变换恢复为单位矩阵，透明色[0, 0, 0, 0]清空画布
+    pub fn clear(&mut self, color: &[u8; 4]) {
+        self.native.clear(color);
+    }
+
+    /// 将画布当前的内容复制为图片，之后继续在画布上绘制不影响这张图片
+    pub fn to_image(&self) -> Image {
+        self.native.to_image()
+    }
+}
+
+impl From<Canvas> for Image {
+    fn from(canvas: Canvas) -> Image {
+        canvas.to_image()
+    }
+}
+
+impl fmt::Debug for Canvas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Canvas")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl Graphics for Canvas {
+    fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
+        self.native.fill_rect(color, x, y, width, height);
+    }
+
+    fn draw_image_with(
+        &mut self,
+        transform: Option<Matrix2D>,
+        image: &Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: &DrawOptions,
+    ) {
+        self.native
+            .draw_image_with(transform, image, src, dest, options);
+    }
+
+    fn draw_text(&mut self, cotnent: &str, x: f64, y: f64, color: &[u8; 4], font_size: u32) {
+        self.native.draw_text(cotnent, x, y, color, font_size);
+    }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.native.fill_polygon(color, points);
+    }
+
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        self.native.stroke_polyline(color, points, closed, style);
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        self.native.fill_path(color, path, rule);
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        self.native.stroke_path(color, path, style);
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.native.fill_polygon_with(brush, points);
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        self.native.fill_path_with(brush, path, rule);
+    }
+
+    fn save(&mut self) {
+        self.native.save();
+    }
+
+    fn restore(&mut self) {
+        self.native.restore();
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.native.transform()
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.native.set_transform(transform);
+    }
+
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        self.native.push_clip(rect);
+    }
+
+    fn pop_clip(&mut self) {
+        self.native.pop_clip();
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.native.set_sampling(sampling);
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.native.sampling()
+    }
+
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        self.native.capture_frame()
+    }
+}
@@ -0,0 +1,130 @@
+use super::{CursorIcon, Event, Settings, WindowCommand};
+use winit::dpi::LogicalSize;
+use winit::{ElementState, KeyboardInput, MouseButton, MouseCursor, VirtualKeyCode, WindowEvent};
+
+/// 按照Settings创建窗口
+pub fn build_window(
+    title: &str,
+    size: (f64, f64),
+    settings: &Settings,
+    events_loop: &winit::EventsLoop,
+) -> winit::Window {
+    let mut builder = winit::WindowBuilder::new()
+        .with_dimensions(LogicalSize::new(size.0, size.1))
+        .with_title(title);
+    if let Some(min_size) = settings.min_size {
+        builder = builder.with_min_dimensions(LogicalSize::new(min_size.width, min_size.height));
+    }
+    if let Some(max_size) = settings.max_size {
+        builder = builder.with_max_dimensions(LogicalSize::new(max_size.width, max_size.height));
+    }
+    if settings.fullscreen {
+        builder = builder.with_fullscreen(Some(events_loop.get_primary_monitor()));
+    }
+    let window = builder.build(events_loop).unwrap();
+
+    if let Some(path) = settings.icon_path {
+        let path = "./static/".to_owned() + path;
+        if let Ok(icon) = winit::Icon::from_path(path) {
+            window.set_window_icon(Some(icon));
+        }
+    }
+    window.hide_cursor(!settings.show_cursor);
+    window
+}
+
+/// 执行Window方法产生的窗口操作
+pub fn apply_command(window: &winit::Window, command: WindowCommand) {
+    match command {
+        WindowCommand::Title(title) => window.set_title(&title),
+        WindowCommand::Fullscreen(fullscreen) => {
+            let monitor = if fullscreen {
+                Some(window.get_current_monitor())
+            } else {
+                None
+            };
+            window.set_fullscreen(monitor);
+        }
+        WindowCommand::Size(width, height) => {
+            window.set_inner_size(LogicalSize::new(width, height))
+        }
+        WindowCommand::CursorVisible(visible) => window.hide_cursor(!visible),
+        WindowCommand::Cursor(icon) => window.set_cursor(match icon {
+            CursorIcon::Default => MouseCursor::Default,
+            CursorIcon::Pointer => MouseCursor::Hand,
+            CursorIcon::Crosshair => MouseCursor::Crosshair,
+            CursorIcon::Text => MouseCursor::Text,
+            CursorIcon::Wait => MouseCursor::Wait,
+            CursorIcon::Progress => MouseCursor::Progress,
+            CursorIcon::Help => MouseCursor::Help,
+            CursorIcon::Move => MouseCursor::Move,
+            CursorIcon::NotAllowed => MouseCursor::NotAllowed,
+            CursorIcon::Grab => MouseCursor::Grab,
+            CursorIcon::Grabbing => MouseCursor::Grabbing,
+            CursorIcon::EwResize => MouseCursor::EwResize,
+            CursorIcon::NsResize => MouseCursor::NsResize,
+        }),
+    }
+}
+
+/// 将winit的按键转换为KeyDown/KeyUp事件
+///
+/// 数字键和小键盘数字键为"0"~"9"，左右Ctrl为"CONTROL"，其他按键使用VirtualKeyCode的名字
+pub fn key_event(input: &KeyboardInput) -> Option<Event> {
+    let vk = input.virtual_keycode?;
+    let key = match vk {
+        VirtualKeyCode::Key0
+        | VirtualKeyCode::Key1
+        | VirtualKeyCode::Key2
+        | VirtualKeyCode::Key3
+        | VirtualKeyCode::Key4
+        | VirtualKeyCode::Key5
+        | VirtualKeyCode::Key6
+        | VirtualKeyCode::Key7
+        | VirtualKeyCode::Key8
+        | VirtualKeyCode::Key9
+        | VirtualKeyCode::Numpad0
+        | VirtualKeyCode::Numpad1
+        | VirtualKeyCode::Numpad2
+        | VirtualKeyCode::Numpad3
+        | VirtualKeyCode::Numpad4
+        | VirtualKeyCode::Numpad5
+        | VirtualKeyCode::Numpad6
+        | VirtualKeyCode::Numpad7
+        | VirtualKeyCode::Numpad8
+        | VirtualKeyCode::Numpad9 => format!("{:?}", vk).replace("Key", "").replace("Numpad", ""),
+        VirtualKeyCode::LControl | VirtualKeyCode::RControl => String::from("CONTROL"),
+        _ => format!("{:?}", vk),
+    };
+    Some(match input.state {
+        ElementState::Pressed => Event::KeyDown(key),
+        ElementState::Released => Event::KeyUp(key),
+    })
+}
+
+/// 将winit的鼠标事件转换为MouseMove/Click事件
+///
+/// mouse_pos保存最后的鼠标位置(窗口坐标)，to_game将窗口坐标转换为游戏坐标
+pub fn mouse_event(
+    event: &WindowEvent,
+    mouse_pos: &mut [f64; 2],
+    to_game: impl Fn(f64, f64) -> (f64, f64),
+) -> Option<Event> {
+    match event {
+        WindowEvent::CursorMoved { position, .. } => {
+            mouse_pos[0] = position.x;
+            mouse_pos[1] = position.y;
+            let (x, y) = to_game(position.x, position.y);
+            Some(Event::MouseMove(x, y))
+        }
+        WindowEvent::MouseInput {
+            state: ElementState::Released,
+            button: MouseButton::Left,
+            ..
+        } => {
+            let (x, y) = to_game(mouse_pos[0], mouse_pos[1]);
+            Some(Event::Click(x, y))
+        }
+        _ => None,
+    }
+}
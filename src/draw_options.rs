@@ -0,0 +1,137 @@
+use super::Matrix2D;
+
+/// 图片和画面的混合方式，和canvas的globalCompositeOperation对应
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    /// 正常覆盖(source-over)
+    #[default]
+    Normal,
+    /// 颜色相加(lighter)，适合发光的粒子
+    Additive,
+    /// 颜色相乘(multiply)，只会变暗
+    Multiply,
+    /// 滤色(screen)，只会变亮
+    Screen,
+}
+
+/// 图片缩放时的采样方式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Sampling {
+    /// 最近邻采样，放大之后像素保持清晰，适合像素风格的游戏
+    Nearest,
+    /// 双线性插值，画面平滑
+    #[default]
+    Linear,
+}
+
+/// 绘制图片的选项
+///
+/// Windows后端的tint和blend需要Direct2D 1.1(Windows 8，或者安装了平台更新的Windows 7)，
+/// 不支持时在CPU上染色(离屏画布转换的图片不染色)，blend按Normal处理
+///
+/// # Example
+///
+/// ```ignore
+/// //受伤时闪红
+/// let options = DrawOptions::new().with_tint([255, 80, 80, 255]);
+/// g.draw_image_with(None, &image, None, Some(dest), &options);
+///
+/// //以图片中心为原点旋转，朝左时水平翻转
+/// let options = DrawOptions::new().with_anchor(0.5, 0.5).with_flip(facing_left, false);
+/// let transform = Matrix2D::translation(x, y).rotate(angle);
+/// g.draw_image_with(Some(transform), &image, None, Some([0., 0., 32., 32.]), &options);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawOptions {
+    /// 不透明度(0~1)
+    pub alpha: f64,
+    /// 和图片颜色相乘的颜色，白色时不改变图片
+    pub tint: [u8; 4],
+    pub blend: BlendMode,
+    /// 水平翻转
+    pub flip_x: bool,
+    /// 垂直翻转
+    pub flip_y: bool,
+    /// 目标区域的(x, y)对应图片中的位置，按图片大小的比例
+    ///
+    /// (0, 0)为左上角，(0.5, 0.5)为中心，(0.5, 1)为底边中点
+    pub anchor: [f64; 2],
+    /// 采样方式，None时使用Graphics::set_sampling设置的方式
+    pub sampling: Option<Sampling>,
+}
+
+impl Default for DrawOptions {
+    fn default() -> DrawOptions {
+        DrawOptions {
+            alpha: 1.0,
+            tint: [255, 255, 255, 255],
+            blend: BlendMode::Normal,
+            flip_x: false,
+            flip_y: false,
+            anchor: [0.0, 0.0],
+            sampling: None,
+        }
+    }
+}
+
+impl DrawOptions {
+    pub fn new() -> DrawOptions {
+        DrawOptions::default()
+    }
+
+    pub fn with_alpha(mut self, alpha: f64) -> DrawOptions {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: [u8; 4]) -> DrawOptions {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_blend(mut self, blend: BlendMode) -> DrawOptions {
+        self.blend = blend;
+        self
+    }
+
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> DrawOptions {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    pub fn with_anchor(mut self, x: f64, y: f64) -> DrawOptions {
+        self.anchor = [x, y];
+        self
+    }
+
+    pub fn with_sampling(mut self, sampling: Sampling) -> DrawOptions {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// 按照anchor移动目标区域，翻转合并到变换中(以目标区域的中心翻转)
+    pub(crate) fn place(
+        &self,
+        transform: Option<Matrix2D>,
+        dest: [f64; 4],
+    ) -> (Option<Matrix2D>, [f64; 4]) {
+        let [x, y, w, h] = dest;
+        let dest = [x - w * self.anchor[0], y - h * self.anchor[1], w, h];
+        if !self.flip_x && !self.flip_y {
+            return (transform, dest);
+        }
+        let (cx, cy) = (dest[0] + w / 2.0, dest[1] + h / 2.0);
+        let sx = if self.flip_x { -1.0 } else { 1.0 };
+        let sy = if self.flip_y { -1.0 } else { 1.0 };
+        let flip = Matrix2D::translation(-cx, -cy)
+            .then(&Matrix2D::scaling(sx, sy))
+            .then(&Matrix2D::translation(cx, cy));
+        (Some(flip.then(&transform.unwrap_or_default())), dest)
+    }
+
+    /// alpha和tint透明度合并之后的不透明度
+    pub(crate) fn opacity(&self) -> f64 {
+        self.alpha.clamp(0.0, 1.0) * self.tint[3] as f64 / 255.0
+    }
+}
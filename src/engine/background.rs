@@ -4,6 +4,12 @@ pub struct ScrollingBackground {
     layers: Vec<BackgroundLayer>,
 }
 
+impl Default for ScrollingBackground {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ScrollingBackground {
     pub fn new() -> ScrollingBackground {
         ScrollingBackground { layers: vec![] }
@@ -59,7 +65,7 @@ impl BackgroundLayer {
                 self.viewport.top += self.speed;
                 self.viewport.bottom += self.speed;
                 if self.viewport.top > self.height() {
-                    self.viewport.bottom = self.viewport.bottom - self.viewport.top;
+                    self.viewport.bottom -= self.viewport.top;
                     self.viewport.top = 0.0;
                 }
             }
@@ -89,7 +95,7 @@ impl BackgroundLayer {
                 self.viewport.left += self.speed;
                 self.viewport.right += self.speed;
                 if self.viewport.left > self.width() {
-                    self.viewport.right = self.viewport.right - self.viewport.left;
+                    self.viewport.right -= self.viewport.left;
                     self.viewport.left = 0.0;
                 }
             }
@@ -97,411 +103,27 @@ impl BackgroundLayer {
     }
 
     pub fn draw(&self, g: &mut impl Graphics) {
-        let (x, y) = (0.0, 0.0);
-        //仅绘制通过视口看到的图层部分
-        if self.viewport.top < 0.0 && self.viewport.left < 0.0 {
-            //绘制分割视口，从上到下，从左到右
-            //绘制左上部分(对应图片右下部分)
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.width() + self.viewport.left,
-                    self.height() + self.viewport.top, //图像源左上角
-                    -self.viewport.left,
-                    -self.viewport.top,
-                ]), //图像源宽高
-                Some([
-                    x,
-                    y, //目标绘制坐标
-                    -self.viewport.left,
-                    -self.viewport.top,
-                ]),
-            );
-            //绘制右上部分(对应图片左下部分)
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    self.height() + self.viewport.top,
-                    -self.viewport.right,
-                    -self.viewport.top,
-                ]),
-                Some([
-                    x - self.viewport.left,
-                    y,
-                    -self.viewport.right,
-                    -self.viewport.top,
-                ]),
-            );
-            //绘制左下部分(对应图片右上部分)
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.width() + self.viewport.left,
-                    0.0,
-                    -self.viewport.left,
-                    self.viewport.bottom,
-                ]),
-                Some([
-                    x,
-                    y - self.viewport.top,
-                    -self.viewport.left,
-                    self.viewport.bottom,
-                ]),
-            );
-            //绘制右下部分(对应图片左上部分)
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([0.0, 0.0, self.viewport.right, self.viewport.bottom]),
-                Some([
-                    x - self.viewport.left,
-                    y - self.viewport.top,
-                    self.viewport.right,
-                    self.viewport.bottom,
-                ]),
-            );
-        } else if self.viewport.top < 0.0 && self.viewport.right > self.width() {
-            //绘制拆开的视口，从顶部环绕到底部，从右侧环绕到左侧
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    self.height() + self.viewport.top,
-                    self.width() - self.viewport.left,
-                    -self.viewport.top,
-                ]),
-                Some([x, y, self.width() - self.viewport.left, -self.viewport.top]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    self.height() + self.viewport.top,
-                    self.viewport.right - self.width(),
-                    -self.viewport.top,
-                ]),
-                Some([
-                    x + (self.width() - self.viewport.left),
-                    y,
-                    self.viewport.right - self.width(),
-                    -self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    0.0,
-                    self.width() - self.viewport.left,
-                    self.viewport.bottom,
-                ]),
-                Some([
-                    x,
-                    y - self.viewport.top,
-                    self.width() - self.viewport.left,
-                    self.viewport.bottom,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    0.0,
-                    self.viewport.right - self.width(),
-                    self.viewport.bottom,
-                ]),
-                Some([
-                    x + (self.width() - self.viewport.left),
-                    y - self.viewport.top,
-                    self.viewport.right - self.width(),
-                    self.viewport.bottom,
-                ]),
-            );
-        } else if self.viewport.bottom > self.height() && self.viewport.left < 0.0 {
-            //绘制拆开的视口，从底部环绕到顶部，从左侧环绕到右侧
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.width() + self.viewport.left,
-                    self.viewport.top,
-                    -self.viewport.left,
-                    self.height() - self.viewport.top,
-                ]),
-                Some([x, y, -self.viewport.left, self.height() - self.viewport.top]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    self.viewport.top,
-                    self.viewport.right,
-                    self.height() - self.viewport.top,
-                ]),
-                Some([
-                    x - self.viewport.left,
-                    y,
-                    self.viewport.right,
-                    self.height() - self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.width() + self.viewport.left,
-                    0.0,
-                    -self.viewport.left,
-                    self.viewport.bottom - self.height(),
-                ]),
-                Some([
-                    x,
-                    y + (self.height() - self.viewport.top),
-                    -self.viewport.left,
-                    self.viewport.bottom - self.height(),
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    0.0,
-                    self.viewport.right,
-                    self.viewport.bottom - self.height(),
-                ]),
-                Some([
-                    x - self.viewport.left,
-                    y + (self.height() - self.viewport.top),
-                    self.viewport.right,
-                    self.viewport.bottom - self.height(),
-                ]),
-            );
-        } else if self.viewport.bottom > self.height() && self.viewport.right > self.width() {
-            //绘制所有窗口，从底部环绕到顶部，从右侧环绕到左侧
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    self.viewport.top,
-                    self.width() - self.viewport.left,
-                    self.height() - self.viewport.top,
-                ]),
-                Some([
-                    x,
-                    y,
-                    self.width() - self.viewport.left,
-                    self.height() - self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    self.viewport.top,
-                    self.viewport.right - self.width(),
-                    self.height() - self.viewport.top,
-                ]),
-                Some([
-                    x + (self.width() - self.viewport.left),
-                    y,
-                    self.viewport.right - self.width(),
-                    self.height() - self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    0.0,
-                    self.width() - self.viewport.left,
-                    self.viewport.bottom - self.height(),
-                ]),
-                Some([
-                    x,
-                    y + (self.height() - self.viewport.top),
-                    self.width() - self.viewport.left,
-                    self.viewport.bottom - self.height(),
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    0.0,
-                    self.viewport.right - self.width(),
-                    self.viewport.bottom - self.height(),
-                ]),
-                Some([
-                    x + (self.width() - self.viewport.left),
-                    y + (self.height() - self.viewport.top),
-                    self.viewport.right - self.width(),
-                    self.viewport.bottom - self.height(),
-                ]),
-            );
-        } else if self.viewport.top < 0.0 {
-            //绘制拆开的视口，从顶部环绕到底部
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    self.height() + self.viewport.top, //srcx, srcY
-                    self.viewport.right - self.viewport.left,
-                    -self.viewport.top,
-                ]), //width, height
-                Some([
-                    x,
-                    y, //destX, destY
-                    self.viewport.right - self.viewport.left,
-                    -self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    0.0, //srcX, srcY
-                    self.viewport.right - self.viewport.left,
-                    self.viewport.bottom,
-                ]),
-                Some([
-                    x,
-                    y - self.viewport.top, //destX, destY
-                    self.viewport.right - self.viewport.left,
-                    self.viewport.bottom,
-                ]),
-            );
-        } else if self.viewport.right > self.width() {
-            //绘制拆开的视口，从右侧环绕到左侧
-            let w = self.width() - self.viewport.left;
-            let h = self.viewport.bottom - self.viewport.top;
-            if w > 0.0 && h > 0.0 {
-                g.draw_image(
-                    None,
-                    &self.bitmap,
-                    Some([self.viewport.left, self.viewport.top, w, h]),
-                    Some([x, y, w, h]),
-                );
+        let (width, height) = (self.width(), self.height());
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        //仅绘制通过视口看到的图层部分，超出图片的部分从另一侧环绕
+        g.push_clip([
+            0.0,
+            0.0,
+            self.viewport.right - self.viewport.left,
+            self.viewport.bottom - self.viewport.top,
+        ]);
+        let mut y = -self.viewport.top.rem_euclid(height);
+        while y < self.viewport.bottom - self.viewport.top {
+            let mut x = -self.viewport.left.rem_euclid(width);
+            while x < self.viewport.right - self.viewport.left {
+                g.draw_image_at(None, &self.bitmap, x, y);
+                x += width;
             }
-
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    self.viewport.top,
-                    self.viewport.right - self.width(),
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-                Some([
-                    x + (self.width() - self.viewport.left),
-                    y,
-                    self.viewport.right - self.width(),
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-            );
-        } else if self.viewport.bottom > self.height() {
-            //绘制拆开的窗口，从底部环绕到顶部
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    self.viewport.top,
-                    self.viewport.right - self.viewport.left,
-                    self.height() - self.viewport.top,
-                ]),
-                Some([
-                    x,
-                    y,
-                    self.viewport.right - self.viewport.left,
-                    self.height() - self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    0.0,
-                    self.viewport.right - self.viewport.left,
-                    self.viewport.bottom - self.height(),
-                ]),
-                Some([
-                    x,
-                    y + (self.height() - self.viewport.top),
-                    self.viewport.right - self.viewport.left,
-                    self.viewport.bottom - self.height(),
-                ]),
-            );
-        } else if self.viewport.left < 0.0 {
-            //绘制拆开的视口，从左侧环绕到右侧
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.width() + self.viewport.left,
-                    self.viewport.top,
-                    -self.viewport.left,
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-                Some([
-                    x,
-                    y,
-                    -self.viewport.left,
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-            );
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    0.0,
-                    self.viewport.top,
-                    self.viewport.right,
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-                Some([
-                    x - self.viewport.left,
-                    y,
-                    self.viewport.right,
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-            );
-        } else {
-            //一次性绘制整个视口
-            g.draw_image(
-                None,
-                &self.bitmap,
-                Some([
-                    self.viewport.left,
-                    self.viewport.top,
-                    self.viewport.right - self.viewport.left,
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-                Some([
-                    x,
-                    y,
-                    self.viewport.right - self.viewport.left,
-                    self.viewport.bottom - self.viewport.top,
-                ]),
-            );
+            y += height;
         }
+        g.pop_clip();
     }
 
     pub fn set_speed(&mut self, speed: f64) {
@@ -10,7 +10,7 @@ pub trait GameEngine {
 
     fn add_sprite(&mut self, sprite: Sprite) {
         let sprites = self.sprites_mut();
-        if sprites.len() > 0 {
+        if !sprites.is_empty() {
             for i in 0..sprites.len() {
                 //根据z-order插入精灵到数组
                 if sprite.z_order() < sprites[i].z_order() {
@@ -64,7 +64,7 @@ pub trait GameEngine {
 
         //删除死亡的精灵
         for sprite_id in sprites_to_kill {
-            self.sprites_mut().retain(|ref s| s.id() != sprite_id);
+            self.sprites_mut().retain(|s| s.id() != sprite_id);
         }
     }
 
@@ -72,16 +72,16 @@ pub trait GameEngine {
         //检查精灵是否和其他精灵相撞
         let sprites = self.sprites_mut();
         let test_sprite = &sprites[test_sprite_id];
-        for i in 0..sprites.len() {
+        for (i, sprite) in sprites.iter().enumerate() {
             //不检查精灵自己
             if i == test_sprite_id {
                 continue;
             }
-            if test_sprite.test_collison(sprites[i].position()) {
+            if test_sprite.test_collison(sprite.position()) {
                 return self.sprite_collision(i, test_sprite_id);
             }
         }
-        return false;
+        false
     }
 
     fn clean_up_sprites(&mut self) {
@@ -89,21 +89,15 @@ pub trait GameEngine {
     }
 
     fn is_point_in_sprite(&mut self, x: f64, y: f64) -> Option<&Sprite> {
-        for sprite in self.sprites() {
-            if !sprite.hidden() && sprite.is_point_inside(x, y) {
-                return Some(sprite);
-            }
-        }
-        None
+        self.sprites()
+            .iter()
+            .find(|sprite| !sprite.hidden() && sprite.is_point_inside(x, y))
     }
 
     fn get_sprite(&mut self, id: f64) -> Option<&mut Sprite> {
-        for sprite in self.sprites_mut() {
-            if sprite.id() == id {
-                return Some(sprite);
-            }
-        }
-        None
+        self.sprites_mut()
+            .iter_mut()
+            .find(|sprite| sprite.id() == id)
     }
 
     fn initialize(&mut self) -> bool {
@@ -1,4 +1,5 @@
 mod background;
+#[allow(clippy::module_inception)]
 mod engine;
 mod sprite;
 
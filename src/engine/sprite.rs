@@ -26,16 +26,16 @@ pub enum Resource {
 
 impl Resource {
     pub fn height(&self) -> f64 {
-        match &self {
-            &Resource::Animation(anim) => anim.frame_height(),
-            &Resource::Static(image) => image.height(),
+        match self {
+            Resource::Animation(anim) => anim.frame_height(),
+            Resource::Static(image) => image.height(),
         }
     }
 
     pub fn width(&self) -> f64 {
-        match &self {
-            &Resource::Animation(anim) => anim.frame_width(),
-            &Resource::Static(image) => image.width(),
+        match self {
+            Resource::Animation(anim) => anim.frame_width(),
+            Resource::Static(image) => image.width(),
         }
     }
 }
@@ -53,6 +53,7 @@ pub struct Sprite {
     bounds_action: BOUNDSACTION,
     hidden: bool,
     dying: bool,
+    facing: Facing,
 }
 
 impl Sprite {
@@ -65,6 +66,11 @@ impl Sprite {
         bounds: Rect,
         bounds_action: BOUNDSACTION,
     ) -> Sprite {
+        //动画的朝向就是精灵的朝向
+        let facing = match &resource {
+            Resource::Animation(anim) => anim.facing(),
+            Resource::Static(_) => Facing::Right,
+        };
         let mut sprite = Sprite {
             id: 0.0,
             name,
@@ -76,12 +82,13 @@ impl Sprite {
                 position.y + resource.height(),
             ),
             resource,
-            velocity: velocity,
-            z_order: z_order,
-            bounds: bounds,
-            bounds_action: bounds_action,
+            velocity,
+            z_order,
+            bounds,
+            bounds_action,
             hidden: false,
             dying: false,
+            facing,
             collision: Rect::zero(),
         };
         sprite.id = current_timestamp() + random();
@@ -152,13 +159,10 @@ impl Sprite {
         // Update the position
         let mut new_position = Point { x: 0.0, y: 0.0 };
         let mut sprite_size = Point { x: 0.0, y: 0.0 };
-        let mut bounds_size = Point { x: 0.0, y: 0.0 };
         new_position.x = self.position.left + self.velocity.x;
         new_position.y = self.position.top + self.velocity.y;
         sprite_size.x = self.position.right - self.position.left;
         sprite_size.y = self.position.bottom - self.position.top;
-        bounds_size.x = self.bounds.right - self.bounds.left;
-        bounds_size.y = self.bounds.bottom - self.bounds.top;
 
         // Check the bounds
         // Wrap?
@@ -255,14 +259,18 @@ impl Sprite {
         if !self.hidden {
             // Draw the appropriate frame, if necessary
             let dest = [
-                self.position.left as f64,
-                self.position.top as f64,
+                self.position.left,
+                self.position.top,
                 self.resource.width(),
                 self.resource.height(),
             ];
             match &self.resource {
+                //动画按自己的朝向翻转，set_facing时已经同步
                 Resource::Animation(anim) => anim.draw(None, g, dest),
-                Resource::Static(image) => g.draw_image(None, &image, None, Some(dest)),
+                Resource::Static(image) => {
+                    let options = DrawOptions::new().with_flip(self.facing == Facing::Left, false);
+                    g.draw_image_with(None, image, None, Some(dest), &options)
+                }
             };
         }
     }
@@ -334,15 +342,24 @@ impl Sprite {
         self.hidden
     }
 
+    /// 朝左时水平翻转绘制，资源是动画时同时设置动画的朝向
+    pub fn set_facing(&mut self, facing: Facing) {
+        self.facing = facing;
+        if let Resource::Animation(anim) = &mut self.resource {
+            anim.set_facing(facing);
+        }
+    }
+
+    pub fn facing(&self) -> Facing {
+        self.facing
+    }
+
     pub fn ext<T: SpriteExt + 'static>(&mut self, sprite_ext: T) {
         self.sprite_ext = Some(Box::new(sprite_ext));
     }
 
     pub fn add_sprite(&self) -> Option<Sprite> {
-        match self.sprite_ext.as_ref() {
-            Some(ext) => Some(ext.add_sprite(self)),
-            _ => None,
-        }
+        self.sprite_ext.as_ref().map(|ext| ext.add_sprite(self))
     }
 
     pub fn id(&self) -> f64 {
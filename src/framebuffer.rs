@@ -0,0 +1,568 @@
+use super::font;
+use super::shape;
+use super::{
+    BlendMode, Brush, DrawOptions, FillRule, GradientStop, Graphics, Image, ImageData, Matrix2D,
+    Path, Sampling, StrokeStyle,
+};
+use image::RgbaImage;
+
+/// 内存中的RGBA帧缓冲，所有绘图由CPU完成
+pub struct Framebuffer {
+    pixels: RgbaImage,
+    //游戏坐标到像素的缩放
+    scale: (f64, f64),
+    transform: Matrix2D,
+    stack: Vec<Matrix2D>,
+    //裁剪区域：像素坐标到裁剪矩形坐标的变换和矩形，不可逆时什么都不显示
+    clips: Vec<(Option<Matrix2D>, [f64; 4])>,
+    sampling: Sampling,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            pixels: RgbaImage::new(width, height),
+            scale: (1.0, 1.0),
+            transform: Matrix2D::IDENTITY,
+            stack: vec![],
+            clips: vec![],
+            sampling: Sampling::Linear,
+        }
+    }
+
+    /// 清空save保存的状态和裁剪区域，变换恢复为单位矩阵
+    pub fn reset_state(&mut self) {
+        self.transform = Matrix2D::IDENTITY;
+        self.stack.clear();
+        self.clips.clear();
+    }
+
+    /// 修改像素大小和游戏坐标的缩放，大小改变时清空画面
+    #[cfg(all(target_os = "linux", not(feature = "headless")))]
+    pub fn resize(&mut self, width: u32, height: u32, scale: (f64, f64)) {
+        if width != self.width() || height != self.height() {
+            self.pixels = RgbaImage::new(width, height);
+        }
+        self.scale = scale;
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    /// 当前帧的像素数据
+    pub fn pixels(&self) -> &RgbaImage {
+        &self.pixels
+    }
+
+    /// 使用指定颜色填充整个帧(不做混合)
+    pub fn clear(&mut self, color: &[u8; 4]) {
+        for pixel in self.pixels.chunks_mut(4) {
+            pixel.copy_from_slice(color);
+        }
+    }
+
+    /// 像素(x, y)的中心是否在所有裁剪区域以内
+    fn in_clip(&self, x: i64, y: i64) -> bool {
+        self.clips
+            .iter()
+            .all(|(inverse, [cx, cy, cw, ch])| match inverse {
+                Some(inverse) => {
+                    let (lx, ly) = inverse.apply(x as f64 + 0.5, y as f64 + 0.5);
+                    lx >= *cx && lx < cx + cw && ly >= *cy && ly < cy + ch
+                }
+                None => false,
+            })
+    }
+
+    /// 按照source-over规则混合一个像素
+    fn blend(&mut self, x: i64, y: i64, color: [u8; 4]) {
+        if x < 0 || y < 0 || x >= self.width() as i64 || y >= self.height() as i64 || color[3] == 0
+        {
+            return;
+        }
+        if !self.clips.is_empty() && !self.in_clip(x, y) {
+            return;
+        }
+        let dst = &mut self.pixels.get_pixel_mut(x as u32, y as u32).0;
+        if color[3] == 255 {
+            *dst = color;
+            return;
+        }
+        let sa = color[3] as u32;
+        let da = dst[3] as u32 * (255 - sa) / 255;
+        let out_a = sa + da;
+        for i in 0..3 {
+            dst[i] = ((color[i] as u32 * sa + dst[i] as u32 * da) / out_a) as u8;
+        }
+        dst[3] = out_a as u8;
+    }
+
+    /// 按照混合方式混合一个像素，和canvas的globalCompositeOperation相同
+    fn blend_mode(&mut self, x: i64, y: i64, color: [u8; 4], mode: BlendMode) {
+        let blend: fn(f64, f64) -> f64 = match mode {
+            BlendMode::Normal => return self.blend(x, y, color),
+            BlendMode::Additive => |s, d| s + d,
+            BlendMode::Multiply => |s, d| s * d,
+            BlendMode::Screen => |s, d| s + d - s * d,
+        };
+        if x < 0 || y < 0 || x >= self.width() as i64 || y >= self.height() as i64 || color[3] == 0
+        {
+            return;
+        }
+        if !self.clips.is_empty() && !self.in_clip(x, y) {
+            return;
+        }
+        let dst = &mut self.pixels.get_pixel_mut(x as u32, y as u32).0;
+        let sa = color[3] as f64 / 255.0;
+        let da = dst[3] as f64 / 255.0;
+        let out_a = if mode == BlendMode::Additive {
+            (sa + da).min(1.0)
+        } else {
+            sa + da * (1.0 - sa)
+        };
+        for i in 0..3 {
+            let (s, d) = (color[i] as f64 / 255.0, dst[i] as f64 / 255.0);
+            //预乘alpha之后的颜色
+            let c = if mode == BlendMode::Additive {
+                s * sa + d * da
+            } else {
+                s * sa * (1.0 - da) + d * da * (1.0 - sa) + sa * da * blend(s, d)
+            };
+            dst[i] = ((c / out_a).min(1.0) * 255.0).round() as u8;
+        }
+        dst[3] = (out_a * 255.0).round() as u8;
+    }
+
+    /// 游戏坐标(先经过local变换)到像素坐标的变换
+    fn pixel_matrix(&self, local: Option<Matrix2D>) -> Matrix2D {
+        local
+            .unwrap_or_default()
+            .then(&self.transform)
+            .then(&Matrix2D::scaling(self.scale.0, self.scale.1))
+    }
+
+    /// 矩形[x, y, w, h]经过变换之后覆盖的像素范围(x0, y0, x1, y1)
+    fn pixel_bounds(&self, matrix: &Matrix2D, rect: [f64; 4]) -> (i64, i64, i64, i64) {
+        let [x, y, w, h] = rect;
+        let corners = [
+            matrix.apply(x, y),
+            matrix.apply(x + w, y),
+            matrix.apply(x, y + h),
+            matrix.apply(x + w, y + h),
+        ];
+        let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+        let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+        for (x, y) in &corners {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        (
+            (min_x.floor() as i64).max(0),
+            (min_y.floor() as i64).max(0),
+            (max_x.ceil() as i64).min(self.width() as i64),
+            (max_y.ceil() as i64).min(self.height() as i64),
+        )
+    }
+
+    /// 对像素中心落在[x0, x1) x [y0, y1)内的像素填色
+    fn fill_area(&mut self, color: [u8; 4], x0: f64, y0: f64, x1: f64, y1: f64) {
+        let (px0, px1) = ((x0 - 0.5).ceil() as i64, (x1 - 0.5).ceil() as i64);
+        let (py0, py1) = ((y0 - 0.5).ceil() as i64, (y1 - 0.5).ceil() as i64);
+        let px0 = px0.max(0);
+        let py0 = py0.max(0);
+        let px1 = px1.min(self.width() as i64);
+        let py1 = py1.min(self.height() as i64);
+        for y in py0..py1 {
+            for x in px0..px1 {
+                self.blend(x, y, color);
+            }
+        }
+    }
+    /// 扫描线填充多个多边形(游戏坐标)，像素中心在内部的像素只混合一次，paint返回像素的颜色
+    fn fill_contours<F: Fn(i64, i64) -> [u8; 4]>(
+        &mut self,
+        contours: &[Vec<[f64; 2]>],
+        rule: FillRule,
+        paint: F,
+    ) {
+        let matrix = self.pixel_matrix(None);
+        let mut edges = vec![];
+        let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+        for contour in contours {
+            let points: Vec<(f64, f64)> =
+                contour.iter().map(|p| matrix.apply(p[0], p[1])).collect();
+            for i in 0..points.len() {
+                let (p0, p1) = (points[i], points[(i + 1) % points.len()]);
+                if p0.1 == p1.1 || !p0.1.is_finite() || !p1.1.is_finite() {
+                    continue;
+                }
+                min_y = min_y.min(p0.1.min(p1.1));
+                max_y = max_y.max(p0.1.max(p1.1));
+                edges.push((p0, p1));
+            }
+        }
+        if edges.is_empty() {
+            return;
+        }
+        let py0 = ((min_y - 0.5).ceil() as i64).max(0);
+        let py1 = ((max_y - 0.5).ceil() as i64).min(self.height() as i64);
+        let mut crossings: Vec<(f64, i32)> = vec![];
+        for py in py0..py1 {
+            let cy = py as f64 + 0.5;
+            crossings.clear();
+            for ((x0, y0), (x1, y1)) in &edges {
+                let winding = if *y0 <= cy && cy < *y1 {
+                    1
+                } else if *y1 <= cy && cy < *y0 {
+                    -1
+                } else {
+                    continue;
+                };
+                crossings.push((x0 + (cy - y0) / (y1 - y0) * (x1 - x0), winding));
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            for i in 0..crossings.len() {
+                winding += crossings[i].1;
+                let inside = match rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+                if inside && i + 1 < crossings.len() {
+                    let px0 = ((crossings[i].0 - 0.5).ceil() as i64).max(0);
+                    let px1 = ((crossings[i + 1].0 - 0.5).ceil() as i64).min(self.width() as i64);
+                    for px in px0..px1 {
+                        self.blend(px, py, paint(px, py));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 使用画刷填充多个多边形，渐变和图案按照像素中心对应的游戏坐标取色
+    fn fill_brush(&mut self, brush: &Brush, contours: &[Vec<[f64; 2]>], rule: FillRule) {
+        let inverse = match self.pixel_matrix(None).invert() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let local = |x: i64, y: i64| inverse.apply(x as f64 + 0.5, y as f64 + 0.5);
+        match brush {
+            Brush::Solid(color) => self.fill_contours(contours, rule, |_, _| *color),
+            Brush::LinearGradient { start, end, stops } => {
+                let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+                let length = dx * dx + dy * dy;
+                if length == 0.0 {
+                    return;
+                }
+                self.fill_contours(contours, rule, |x, y| {
+                    let (x, y) = local(x, y);
+                    let t = ((x - start[0]) * dx + (y - start[1]) * dy) / length;
+                    gradient_color(stops, t)
+                });
+            }
+            Brush::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                if *radius <= 0.0 {
+                    return;
+                }
+                self.fill_contours(contours, rule, |x, y| {
+                    let (x, y) = local(x, y);
+                    let t = (x - center[0]).hypot(y - center[1]) / radius;
+                    gradient_color(stops, t)
+                });
+            }
+            //图案使用最近邻采样
+            Brush::Pattern { image, transform } => {
+                let bitmap = match image.native::<RgbaImage>() {
+                    Some(bitmap) if bitmap.width() > 0 && bitmap.height() > 0 => bitmap,
+                    _ => return,
+                };
+                let pattern = match transform.invert() {
+                    Some(pattern) => pattern,
+                    None => return,
+                };
+                let (w, h) = (bitmap.width() as i64, bitmap.height() as i64);
+                self.fill_contours(contours, rule, |x, y| {
+                    let (x, y) = local(x, y);
+                    let (u, v) = pattern.apply(x, y);
+                    let u = (u.floor() as i64).rem_euclid(w);
+                    let v = (v.floor() as i64).rem_euclid(h);
+                    bitmap.get_pixel(u as u32, v as u32).0
+                });
+            }
+        }
+    }
+}
+
+impl Graphics for Framebuffer {
+    fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
+        let matrix = self.pixel_matrix(None);
+        if matrix.b == 0.0 && matrix.c == 0.0 {
+            //没有旋转和斜切，直接填充
+            let (x0, y0) = matrix.apply(x, y);
+            let (x1, y1) = matrix.apply(x + width, y + height);
+            self.fill_area(*color, x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+            return;
+        }
+        let inverse = match matrix.invert() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let (x0, y0, x1, y1) = self.pixel_bounds(&matrix, [x, y, width, height]);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let (lx, ly) = inverse.apply(px as f64 + 0.5, py as f64 + 0.5);
+                if lx >= x && lx < x + width && ly >= y && ly < y + height {
+                    self.blend(px, py, *color);
+                }
+            }
+        }
+    }
+
+    fn draw_image_with(
+        &mut self,
+        transform: Option<Matrix2D>,
+        image: &Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: &DrawOptions,
+    ) {
+        let (w, h) = (image.width(), image.height());
+        let src = src.unwrap_or([0.0, 0.0, w, h]);
+        let (transform, dest) = options.place(transform, dest.unwrap_or([0.0, 0.0, w, h]));
+        if dest[2] == 0.0 || dest[3] == 0.0 || src[2] <= 0.0 || src[3] <= 0.0 {
+            return;
+        }
+        let matrix = self.pixel_matrix(transform);
+        let inverse = match matrix.invert() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        let (x0, y0, x1, y1) = self.pixel_bounds(&matrix, dest);
+
+        //源区域限制在图片范围之内
+        let bitmap = match image.native::<RgbaImage>() {
+            Some(bitmap) => bitmap,
+            None => return,
+        };
+        let sx0 = src[0].max(0.0) as u32;
+        let sy0 = src[1].max(0.0) as u32;
+        let sx1 = ((src[0] + src[2]).ceil() as u32).min(bitmap.width());
+        let sy1 = ((src[1] + src[3]).ceil() as u32).min(bitmap.height());
+        if sx0 >= sx1 || sy0 >= sy1 {
+            return;
+        }
+        let opacity = options.opacity();
+        let tint = options.tint;
+        let sampling = options.sampling.unwrap_or(self.sampling);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                //像素中心反向映射到目标矩形
+                let (lx, ly) = inverse.apply(x as f64 + 0.5, y as f64 + 0.5);
+                let u = (lx - dest[0]) / dest[2];
+                let v = (ly - dest[1]) / dest[3];
+                if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                    continue;
+                }
+                let (fx, fy) = (src[0] + u * src[2], src[1] + v * src[3]);
+                let mut color = match sampling {
+                    Sampling::Nearest => {
+                        let sx = (fx as u32).max(sx0).min(sx1 - 1);
+                        let sy = (fy as u32).max(sy0).min(sy1 - 1);
+                        bitmap.get_pixel(sx, sy).0
+                    }
+                    Sampling::Linear => bilinear(bitmap, fx, fy, (sx0, sy0, sx1, sy1)),
+                };
+                if tint != [255, 255, 255, 255] || opacity < 1.0 {
+                    for i in 0..3 {
+                        color[i] = (color[i] as u32 * tint[i] as u32 / 255) as u8;
+                    }
+                    color[3] = (color[3] as f64 * opacity).round() as u8;
+                }
+                self.blend_mode(x, y, color, options.blend);
+            }
+        }
+    }
+
+    fn draw_text(&mut self, cotnent: &str, x: f64, y: f64, color: &[u8; 4], font_size: u32) {
+        //点阵字体按字号缩放
+        let scale = font_size as f64 / font::CELL_HEIGHT as f64;
+        let mut cursor = x;
+        for c in cotnent.chars() {
+            let glyph = font::glyph(c);
+            for (col, bits) in glyph.iter().enumerate() {
+                for row in 0..font::CELL_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        let px = cursor + col as f64 * scale;
+                        let py = y + row as f64 * scale;
+                        self.fill_rect(color, px, py, scale, scale);
+                    }
+                }
+            }
+            cursor += font::CELL_WIDTH as f64 * scale;
+        }
+    }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.fill_contours(&[points.to_vec()], FillRule::NonZero, |_, _| *color);
+    }
+
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        let scale = shape::transform_scale(&self.pixel_matrix(None));
+        let contours = shape::stroke_polygons(points, closed, style, scale);
+        self.fill_contours(&contours, FillRule::NonZero, |_, _| *color);
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        let scale = shape::transform_scale(&self.pixel_matrix(None));
+        let contours: Vec<Vec<[f64; 2]>> = path
+            .flatten(scale)
+            .into_iter()
+            .map(|(points, _)| points)
+            .collect();
+        self.fill_contours(&contours, rule, |_, _| *color);
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        let scale = shape::transform_scale(&self.pixel_matrix(None));
+        let mut contours = vec![];
+        for (points, closed) in path.flatten(scale) {
+            contours.extend(shape::stroke_polygons(&points, closed, style, scale));
+        }
+        self.fill_contours(&contours, FillRule::NonZero, |_, _| *color);
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.fill_brush(brush, &[points.to_vec()], FillRule::NonZero);
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        let scale = shape::transform_scale(&self.pixel_matrix(None));
+        let contours: Vec<Vec<[f64; 2]>> = path
+            .flatten(scale)
+            .into_iter()
+            .map(|(points, _)| points)
+            .collect();
+        self.fill_brush(brush, &contours, rule);
+    }
+
+    fn save(&mut self) {
+        self.stack.push(self.transform);
+    }
+
+    fn restore(&mut self) {
+        if let Some(transform) = self.stack.pop() {
+            self.transform = transform;
+        }
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.transform = transform;
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        let inverse = self.pixel_matrix(None).invert();
+        self.clips.push((inverse, rect));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        Some(ImageData::new(
+            self.width(),
+            self.height(),
+            self.pixels.clone().into_raw(),
+        ))
+    }
+}
+
+/// 双线性插值采样(x, y)处的颜色，只使用bounds(x0, y0, x1, y1)以内的像素
+fn bilinear(bitmap: &RgbaImage, x: f64, y: f64, bounds: (u32, u32, u32, u32)) -> [u8; 4] {
+    let (x0, y0, x1, y1) = bounds;
+    //以像素中心为采样点
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (fx, fy) = (x.floor(), y.floor());
+    let (tx, ty) = (x - fx, y - fy);
+    let clamp_x = |x: f64| (x.max(x0 as f64) as u32).min(x1 - 1);
+    let clamp_y = |y: f64| (y.max(y0 as f64) as u32).min(y1 - 1);
+    let (left, right) = (clamp_x(fx), clamp_x(fx + 1.0));
+    let (top, bottom) = (clamp_y(fy), clamp_y(fy + 1.0));
+    let samples = [
+        (bitmap.get_pixel(left, top).0, (1.0 - tx) * (1.0 - ty)),
+        (bitmap.get_pixel(right, top).0, tx * (1.0 - ty)),
+        (bitmap.get_pixel(left, bottom).0, (1.0 - tx) * ty),
+        (bitmap.get_pixel(right, bottom).0, tx * ty),
+    ];
+    //按alpha加权，避免透明像素的颜色混进来
+    let mut sum = [0.0; 4];
+    for (pixel, weight) in &samples {
+        let alpha = pixel[3] as f64 * weight;
+        for i in 0..3 {
+            sum[i] += pixel[i] as f64 * alpha;
+        }
+        sum[3] += alpha;
+    }
+    if sum[3] <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    [
+        (sum[0] / sum[3]).round() as u8,
+        (sum[1] / sum[3]).round() as u8,
+        (sum[2] / sum[3]).round() as u8,
+        sum[3].round() as u8,
+    ]
+}
+
+/// 渐变中位置t的颜色，在相邻的两个节点之间线性插值，没有节点时为透明
+fn gradient_color(stops: &[GradientStop], t: f64) -> [u8; 4] {
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return [0, 0, 0, 0],
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+    for pair in stops.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if t < b.offset {
+            let k = (t - a.offset) / (b.offset - a.offset);
+            let mix = |i: usize| {
+                (a.color[i] as f64 + (b.color[i] as f64 - a.color[i] as f64) * k).round() as u8
+            };
+            return [mix(0), mix(1), mix(2), mix(3)];
+        }
+    }
+    last.color
+}
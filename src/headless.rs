@@ -0,0 +1,528 @@
+use super::framebuffer::Framebuffer;
+use super::timing;
+use super::{
+    Assets, AssetsType, AudioType, Backend, Brush, Canvas, CursorIcon, DrawOptions, Event,
+    FillRule, FixedTimestep, FrameTime, Graphics, Image, ImageData, Matrix2D, NativeCanvas,
+    NativeImage, Path, Sampling, Settings, Sound, State, StrokeStyle, Window, WindowCommand,
+};
+use image::RgbaImage;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::Read;
+use std::io::{Error, ErrorKind, Result};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 内存中的图片，绘制时由CPU完成
+impl NativeImage for RgbaImage {
+    fn width(&self) -> f64 {
+        RgbaImage::width(self) as f64
+    }
+    fn height(&self) -> f64 {
+        RgbaImage::height(self) as f64
+    }
+}
+
+enum RawAssets {
+    Image(RgbaImage),
+    Blob(Vec<u8>),
+}
+
+/// 读取static目录下的资源文件
+fn read_assets(path: &str, tp: AssetsType) -> Result<RawAssets> {
+    let mut buf = vec![];
+    File::open("./static/".to_owned() + path)?.read_to_end(&mut buf)?;
+    match tp {
+        AssetsType::Image => {
+            let mut rgba_image = image::load_from_memory(&buf)
+                .map_err(|_| Error::other("图片读取失败"))?
+                .to_rgba();
+            //将白色透明转换为黑色透明
+            for pixel in rgba_image.chunks_mut(4) {
+                if pixel[3] == 0 {
+                    pixel.copy_from_slice(&[0, 0, 0, 0])
+                }
+            }
+            Ok(RawAssets::Image(rgba_image))
+        }
+        _ => Ok(RawAssets::Blob(buf)),
+    }
+}
+
+/// 资源同步读取的Window实现，在下一次循环时交给State
+///
+/// 无窗口后端和Linux桌面后端共用
+pub struct HeadlessWindow {
+    timestep: FixedTimestep,
+    loaded: Vec<(String, AssetsType, Result<RawAssets>)>,
+    commands: Vec<WindowCommand>,
+    quit: bool,
+    scale_factor: f64,
+    sampling: Sampling,
+}
+
+impl HeadlessWindow {
+    pub(crate) fn new(settings: &Settings) -> HeadlessWindow {
+        HeadlessWindow {
+            timestep: FixedTimestep::new(settings.ups, settings.max_updates_per_frame),
+            loaded: vec![],
+            commands: vec![],
+            quit: false,
+            scale_factor: 1.0,
+            sampling: settings.sampling,
+        }
+    }
+
+    /// 屏幕缩放比例改变时由Linux桌面后端调用
+    #[cfg(all(target_os = "linux", not(feature = "headless")))]
+    pub(crate) fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// 取出等待执行的窗口操作
+    pub(crate) fn take_commands(&mut self) -> Vec<WindowCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    /// 是否调用了quit
+    pub fn is_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// 时间前进到now(毫秒)，按照固定步长调用update，返回调用的次数
+    pub(crate) fn run_updates<S: State>(&mut self, game: &mut S, now: f64) -> u32 {
+        let updates = self.timestep.advance(now);
+        for _ in 0..updates {
+            let time = self.timestep.step();
+            game.update(self, time);
+        }
+        updates
+    }
+
+    /// 绘制使用的时间信息
+    pub(crate) fn frame_time(&self) -> FrameTime {
+        self.timestep.frame_time()
+    }
+
+    /// 距离下一次update的时间
+    pub(crate) fn next_update_delay(&self) -> Duration {
+        let time = self.timestep.frame_time();
+        Duration::from_secs_f64(time.dt * (1.0 - time.alpha))
+    }
+
+    /// 将已读取的资源分发给State
+    pub(crate) fn dispatch_assets<S: State>(&mut self, game: &mut S) {
+        while !self.loaded.is_empty() {
+            let (path, tp, data) = self.loaded.remove(0);
+            let assets = match data {
+                Ok(RawAssets::Image(image)) => Ok(Assets::Image(Image::new(image))),
+                Ok(RawAssets::Blob(buffer)) => match tp {
+                    AssetsType::Sound => {
+                        Ok(Assets::Sound(Sound::new(AudioType::test(&path), buffer)))
+                    }
+                    _ => Ok(Assets::File(buffer)),
+                },
+                Err(err) => Err(err),
+            };
+            game.on_assets_load(&path, tp, assets, self);
+        }
+    }
+}
+
+impl Window for HeadlessWindow {
+    fn set_update_rate(&mut self, ups: u64) {
+        self.timestep.set_ups(ups);
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.commands.push(WindowCommand::Title(title.to_string()));
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.commands.push(WindowCommand::Fullscreen(fullscreen));
+    }
+
+    fn set_size(&mut self, width: f64, height: f64) {
+        self.commands.push(WindowCommand::Size(width, height));
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.commands.push(WindowCommand::CursorVisible(visible));
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.commands.push(WindowCommand::Cursor(icon));
+    }
+
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn load_assets(&mut self, assets: &[(&str, AssetsType)]) {
+        for (path, tp) in assets {
+            let data = read_assets(path, *tp);
+            self.loaded.push((path.to_string(), *tp, data));
+        }
+    }
+
+    fn load_image_data(&mut self, key: &str, image: ImageData) {
+        let image = match RgbaImage::from_raw(image.width, image.height, image.data) {
+            Some(image) => Ok(RawAssets::Image(image)),
+            None => Err(Error::new(ErrorKind::InvalidData, "图片数据长度错误")),
+        };
+        self.loaded
+            .push((String::from(key), AssetsType::Image, image));
+    }
+
+    fn load_svg(&mut self, key: &str, svg: String) {
+        let image = nsvg::parse_str(&svg, nsvg::Units::Pixel, 96.0)
+            .and_then(|svg| svg.rasterize_to_raw_rgba(1.0))
+            .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{:?}", err)))
+            .and_then(|(width, height, raw_rgba)| {
+                RgbaImage::from_raw(width, height, raw_rgba)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "SVG渲染失败"))
+            })
+            .map(RawAssets::Image);
+        self.loaded
+            .push((String::from(key), AssetsType::Image, image));
+    }
+
+    fn create_canvas(&self, width: f64, height: f64) -> Canvas {
+        let mut graphics = HeadlessGraphics::new(width.ceil() as u32, height.ceil() as u32);
+        graphics.set_sampling(self.sampling);
+        Canvas::new(width, height, graphics)
+    }
+}
+
+/// 绘制到内存帧缓冲的Graphics
+///
+/// 无窗口后端和Linux桌面后端共用
+pub struct HeadlessGraphics {
+    framebuffer: Framebuffer,
+}
+
+impl HeadlessGraphics {
+    pub(crate) fn new(width: u32, height: u32) -> HeadlessGraphics {
+        HeadlessGraphics {
+            framebuffer: Framebuffer::new(width, height),
+        }
+    }
+
+    /// 修改画面的像素大小，scale为游戏坐标到像素的缩放
+    #[cfg(all(target_os = "linux", not(feature = "headless")))]
+    pub(crate) fn resize(&mut self, width: u32, height: u32, scale: (f64, f64)) {
+        self.framebuffer.resize(width, height, scale);
+    }
+
+    /// 用背景色清空画面，并重置变换
+    pub(crate) fn clear(&mut self, color: &[u8; 4]) {
+        self.framebuffer.clear(color);
+        self.reset_state();
+    }
+
+    /// 清空save保存的状态，变换恢复为单位矩阵
+    pub(crate) fn reset_state(&mut self) {
+        self.framebuffer.reset_state();
+    }
+
+    /// 最后一次绘制的画面
+    pub fn frame(&self) -> &RgbaImage {
+        self.framebuffer.pixels()
+    }
+}
+
+impl Graphics for HeadlessGraphics {
+    fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
+        self.framebuffer.fill_rect(color, x, y, width, height);
+    }
+
+    fn draw_image_with(
+        &mut self,
+        transform: Option<Matrix2D>,
+        image: &Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: &DrawOptions,
+    ) {
+        self.framebuffer
+            .draw_image_with(transform, image, src, dest, options);
+    }
+
+    fn draw_text(&mut self, cotnent: &str, x: f64, y: f64, color: &[u8; 4], font_size: u32) {
+        self.framebuffer.draw_text(cotnent, x, y, color, font_size);
+    }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.framebuffer.fill_polygon(color, points);
+    }
+
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        self.framebuffer
+            .stroke_polyline(color, points, closed, style);
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        self.framebuffer.fill_path(color, path, rule);
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        self.framebuffer.stroke_path(color, path, style);
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.framebuffer.fill_polygon_with(brush, points);
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        self.framebuffer.fill_path_with(brush, path, rule);
+    }
+
+    fn save(&mut self) {
+        self.framebuffer.save();
+    }
+
+    fn restore(&mut self) {
+        self.framebuffer.restore();
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.framebuffer.transform()
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.framebuffer.set_transform(transform);
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.framebuffer.set_sampling(sampling);
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.framebuffer.sampling()
+    }
+
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        self.framebuffer.push_clip(rect);
+    }
+
+    fn pop_clip(&mut self) {
+        self.framebuffer.pop_clip();
+    }
+
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        self.framebuffer.capture_frame()
+    }
+}
+
+/// 离屏画布，内容复制为RgbaImage
+impl NativeCanvas for HeadlessGraphics {
+    fn clear(&mut self, color: &[u8; 4]) {
+        HeadlessGraphics::clear(self, color);
+    }
+
+    fn to_image(&self) -> Image {
+        Image::new(self.frame().clone())
+    }
+}
+
+/// 无窗口后端，不显示窗口，按照Settings中的ups更新并在内存中绘制
+pub struct HeadlessBackend;
+
+impl Backend for HeadlessBackend {
+    type Image = RgbaImage;
+    type Sound = Vec<u8>;
+    type Window = HeadlessWindow;
+    type Graphics = HeadlessGraphics;
+
+    fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+        run::<S>(title, width, height, settings);
+    }
+
+    /// 无声音设备，忽略
+    fn play_sound(_sound: &Vec<u8>) {}
+}
+
+fn run<S: State>(_title: &str, width: f64, height: f64, settings: Settings) {
+    let mut graphics = HeadlessGraphics::new(width as u32, height as u32);
+    graphics.set_sampling(settings.sampling);
+    let background_color = settings.background_color.unwrap_or([0, 0, 0, 255]);
+    let frame_delay = Duration::from_micros(1000 * 1000 / 60);
+
+    let mut game_window = HeadlessWindow::new(&settings);
+    let mut game = S::new(&mut game_window);
+    let timer = Instant::now();
+    let mut next_draw_time = timer.elapsed();
+    while !game_window.quit {
+        game_window.dispatch_assets(&mut game);
+        game_window.run_updates(&mut game, timer.elapsed().as_secs_f64() * 1000.0);
+        //没有窗口，忽略窗口操作
+        game_window.take_commands();
+        if timer.elapsed() >= next_draw_time {
+            next_draw_time += frame_delay;
+            graphics.clear(&background_color);
+            let time = game_window.frame_time();
+            game.draw(&mut graphics, &mut game_window, time);
+        }
+        let next = (timer.elapsed() + game_window.next_update_delay()).min(next_draw_time);
+        let now = timer.elapsed();
+        if next > now {
+            thread::sleep(next - now);
+        }
+    }
+}
+
+thread_local! {
+    /// Runner使用的虚拟时钟(毫秒)，为None时使用系统时间
+    static VIRTUAL_CLOCK: Cell<Option<f64>> = const { Cell::new(None) };
+    /// Runner使用的固定种子随机数
+    static SEEDED_RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// 逐步驱动State的测试工具，时间和随机数都是确定的
+///
+/// # Example
+///
+/// ```ignore
+/// let mut runner = mengine::run_headless::<Game>(300., 300., Settings::default());
+/// runner.event(Event::KeyDown(String::from("SPACE")));
+/// runner.step(60);
+/// assert_eq!(runner.frame().get_pixel(10, 10).0, [0, 0, 0, 255]);
+/// ```
+pub struct Runner<S: State> {
+    game: S,
+    window: HeadlessWindow,
+    graphics: HeadlessGraphics,
+    background_color: [u8; 4],
+}
+
+/// 创建不显示窗口的Runner，每次更新时钟前进 1000/ups 毫秒，随机数种子固定为0
+pub fn run_headless<S: State>(width: f64, height: f64, settings: Settings) -> Runner<S> {
+    VIRTUAL_CLOCK.with(|clock| clock.set(Some(0.0)));
+    SEEDED_RNG.with(|rng| *rng.borrow_mut() = Some(StdRng::seed_from_u64(0)));
+    timing::reset_game_clock();
+    let mut window = HeadlessWindow::new(&settings);
+    window.timestep.advance(0.0);
+    let mut game = S::new(&mut window);
+    window.dispatch_assets(&mut game);
+    let mut graphics = HeadlessGraphics::new(width as u32, height as u32);
+    graphics.set_sampling(settings.sampling);
+    Runner {
+        game,
+        window,
+        graphics,
+        background_color: settings.background_color.unwrap_or([0, 0, 0, 255]),
+    }
+}
+
+impl<S: State> Runner<S> {
+    /// 时钟前进n个update周期，然后绘制一帧
+    ///
+    /// 游戏时钟正常运行时调用n次update，暂停或者慢动作时调用的次数会减少
+    pub fn step(&mut self, updates: u32) {
+        for _ in 0..updates {
+            let delay = self.window.timestep.dt() * 1000.0;
+            let now = VIRTUAL_CLOCK.with(|clock| {
+                let now = clock.get().unwrap_or(0.0) + delay;
+                clock.set(Some(now));
+                now
+            });
+            self.window.run_updates(&mut self.game, now);
+            self.window.dispatch_assets(&mut self.game);
+        }
+        self.window.take_commands();
+        self.graphics.clear(&self.background_color);
+        let time = self.window.frame_time();
+        self.game.draw(&mut self.graphics, &mut self.window, time);
+        self.window.dispatch_assets(&mut self.game);
+    }
+
+    /// 向State发送事件
+    pub fn event(&mut self, event: Event) {
+        self.game.event(event, &mut self.window);
+        self.window.dispatch_assets(&mut self.game);
+    }
+
+    /// 模拟用户关闭窗口，State::on_exit返回true时退出
+    pub fn close(&mut self) -> bool {
+        let exit = self.game.on_exit(&mut self.window);
+        if exit {
+            self.window.quit = true;
+        }
+        exit
+    }
+
+    /// 最后一次绘制的画面
+    pub fn frame(&self) -> &RgbaImage {
+        self.graphics.frame()
+    }
+
+    /// 当前的虚拟时间(毫秒)
+    pub fn timestamp(&self) -> f64 {
+        current_timestamp()
+    }
+
+    pub fn state(&self) -> &S {
+        &self.game
+    }
+
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.game
+    }
+
+    pub fn window(&mut self) -> &mut HeadlessWindow {
+        &mut self.window
+    }
+}
+
+impl<S: State> Drop for Runner<S> {
+    fn drop(&mut self) {
+        VIRTUAL_CLOCK.with(|clock| clock.set(None));
+        SEEDED_RNG.with(|rng| *rng.borrow_mut() = None);
+        timing::reset_game_clock();
+    }
+}
+
+pub fn current_timestamp() -> f64 {
+    if let Some(now) = VIRTUAL_CLOCK.with(|clock| clock.get()) {
+        return now;
+    }
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as f64
+}
+
+pub fn random() -> f64 {
+    SEEDED_RNG.with(|rng| match rng.borrow_mut().as_mut() {
+        Some(rng) => rng.gen::<f64>(),
+        None => rand::random::<f64>(),
+    })
+}
+
+pub fn log<T: std::fmt::Debug>(s: T) {
+    println!("{:?}", s);
+}
+
+//Linux桌面后端使用linux模块中的音乐播放
+#[cfg(not(all(target_os = "linux", not(feature = "headless"))))]
+pub fn play_music(_file: &str, _repeat: bool) {}
+
+#[cfg(not(all(target_os = "linux", not(feature = "headless"))))]
+pub fn stop_music() {}
+
+pub fn alert(head: &str, msg: &str) {
+    eprintln!("{}: {}", head, msg);
+}
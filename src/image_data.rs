@@ -0,0 +1,158 @@
+/// RGBA像素数据
+///
+/// 和后端无关，可以在CPU上读写像素，通过Window::load_image_data上传为Image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    /// 按行排列的RGBA数据，长度为 width * height * 4
+    pub data: Vec<u8>,
+}
+
+impl ImageData {
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> ImageData {
+        assert_eq!(data.len(), byte_len(width, height));
+        ImageData {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// 创建填充为指定颜色的图片
+    pub fn from_color(width: u32, height: u32, color: [u8; 4]) -> ImageData {
+        let data = color
+            .iter()
+            .cycle()
+            .take(byte_len(width, height))
+            .cloned()
+            .collect();
+        ImageData::new(width, height, data)
+    }
+
+    /// 从BGRA数据创建
+    pub fn from_bgra(width: u32, height: u32, mut data: Vec<u8>) -> ImageData {
+        for pixel in data.chunks_mut(4) {
+            pixel.swap(0, 2);
+        }
+        ImageData::new(width, height, data)
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "像素({}, {})超出图片范围{}x{}",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    /// 读取像素，坐标超出范围时panic
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// 修改像素，坐标超出范围时panic
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        let i = self.index(x, y);
+        self.data[i..i + 4].copy_from_slice(&color);
+    }
+
+    /// 复制一部分图片，超出范围的部分会被裁掉
+    pub fn sub_image(&self, x: u32, y: u32, width: u32, height: u32) -> ImageData {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+        let mut data = Vec::with_capacity(byte_len(width, height));
+        for row in y..y + height {
+            let start = (row as usize * self.width as usize + x as usize) * 4;
+            data.extend_from_slice(&self.data[start..start + width as usize * 4]);
+        }
+        ImageData::new(width, height, data)
+    }
+
+    /// 水平翻转(左右镜像)
+    pub fn flip_horizontal(&self) -> ImageData {
+        self.map_pixels(self.width, self.height, |x, y| (self.width - 1 - x, y))
+    }
+
+    /// 垂直翻转(上下镜像)
+    pub fn flip_vertical(&self) -> ImageData {
+        self.map_pixels(self.width, self.height, |x, y| (x, self.height - 1 - y))
+    }
+
+    /// 顺时针旋转90度
+    pub fn rotate90(&self) -> ImageData {
+        self.map_pixels(self.height, self.width, |x, y| (y, self.height - 1 - x))
+    }
+
+    /// 缩放到指定大小(最近邻采样)
+    pub fn scale(&self, width: u32, height: u32) -> ImageData {
+        if self.width == 0 || self.height == 0 {
+            return ImageData::new(width, height, vec![0; byte_len(width, height)]);
+        }
+        self.map_pixels(width, height, |x, y| {
+            (
+                (x as u64 * self.width as u64 / width as u64) as u32,
+                (y as u64 * self.height as u64 / height as u64) as u32,
+            )
+        })
+    }
+
+    /// 创建新的图片，source返回新图片中每个像素在原图中的坐标
+    fn map_pixels(
+        &self,
+        width: u32,
+        height: u32,
+        source: impl Fn(u32, u32) -> (u32, u32),
+    ) -> ImageData {
+        let mut data = Vec::with_capacity(byte_len(width, height));
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = source(x, y);
+                data.extend_from_slice(&self.get_pixel(sx, sy));
+            }
+        }
+        ImageData::new(width, height, data)
+    }
+
+    /// 编码为PNG
+    pub fn to_png(&self) -> std::io::Result<Vec<u8>> {
+        let mut png_data: Vec<u8> = vec![];
+        {
+            let mut encoder = png::Encoder::new(&mut png_data, self.width, self.height);
+            encoder.set_color(png::ColorType::RGBA);
+            let mut writer = encoder
+                .write_header()
+                .map_err(|err| std::io::Error::other(format!("{:?}", err)))?;
+            writer
+                .write_image_data(&self.data)
+                .map_err(|err| std::io::Error::other(format!("{:?}", err)))?;
+        }
+        Ok(png_data)
+    }
+
+    /// 保存为PNG文件
+    #[cfg(not(any(target_arch = "asmjs", target_arch = "wasm32")))]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_png()?)
+    }
+}
+
+/// 像素数据的字节数，在usize中计算避免u32溢出
+fn byte_len(width: u32, height: u32) -> usize {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|len| len.checked_mul(4))
+        .unwrap_or_else(|| panic!("图片{}x{}太大", width, height))
+}
@@ -4,34 +4,101 @@
 #[macro_use]
 extern crate stdweb;
 
-#[cfg(windows)]
+#[cfg(all(windows, not(feature = "headless")))]
 mod windows;
-#[cfg(windows)]
+#[cfg(all(windows, not(feature = "headless")))]
 use windows as window;
 
 #[cfg(any(target_arch = "asmjs", target_arch = "wasm32"))]
 mod web;
+use std::f64::consts::PI;
 use std::rc::Rc;
 #[cfg(any(target_arch = "asmjs", target_arch = "wasm32"))]
 use web as window;
-pub mod engine;
-
-pub use window::{
-    alert, current_timestamp, log, play_music, play_sound, random, run, stop_music, Image, Sound,
-};
 
-pub struct Transform {
-    pub rotate: f64,
-    pub translate: (f64, f64),
-}
-impl Default for Transform {
-    fn default() -> Self {
-        Transform {
-            rotate: 0.0,
-            translate: (0.0, 0.0),
-        }
-    }
-}
+/// 无窗口后端和软件渲染：非Windows的桌面平台使用，或者通过headless特性启用
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+mod headless;
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+use headless as window;
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+mod font;
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+mod framebuffer;
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+mod snapshot;
+
+/// Linux桌面后端：使用winit窗口显示软件渲染的画面
+#[cfg(all(target_os = "linux", not(feature = "headless")))]
+mod linux;
+/// windows和linux桌面后端共用的winit代码
+#[cfg(all(any(windows, target_os = "linux"), not(feature = "headless")))]
+mod desktop;
+
+mod backend;
+mod brush;
+mod canvas;
+mod draw_options;
+pub mod engine;
+mod image_data;
+mod matrix;
+mod path;
+#[cfg(not(any(target_arch = "asmjs", target_arch = "wasm32")))]
+mod recorder;
+mod recording;
+mod shape;
+mod timing;
+mod viewport;
+
+pub use backend::{play_sound, run, run_with, Backend, DefaultBackend, Image, NativeImage, Sound};
+pub use brush::{Brush, GradientStop};
+pub use canvas::{Canvas, NativeCanvas};
+pub use draw_options::{BlendMode, DrawOptions, Sampling};
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+pub use headless::{run_headless, HeadlessBackend, HeadlessGraphics, HeadlessWindow, Runner};
+pub use image_data::ImageData;
+#[cfg(all(target_os = "linux", not(feature = "headless")))]
+pub use linux::LinuxBackend;
+#[cfg(all(target_os = "linux", not(feature = "headless")))]
+pub use linux::{play_music, stop_music};
+pub use matrix::Matrix2D;
+pub use path::{FillRule, Path, PathCommand};
+#[cfg(not(any(target_arch = "asmjs", target_arch = "wasm32")))]
+pub use recorder::Recorder;
+pub use recording::{DrawCommand, RecordingGraphics};
+pub use shape::{LineCap, StrokeStyle};
+pub use timing::{game_timestamp, FixedTimestep, FrameTime};
+pub use viewport::{ScaleMode, Viewport};
+#[cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+pub use snapshot::{assert_snapshot, compare_snapshot, render_frames};
+#[cfg(any(target_arch = "asmjs", target_arch = "wasm32"))]
+pub use web::{BrowserBackend, BrowserGraphics, BrowserWindow};
+pub use window::{alert, current_timestamp, log, random};
+#[cfg(not(all(target_os = "linux", not(feature = "headless"))))]
+pub use window::{play_music, stop_music};
+#[cfg(all(windows, not(feature = "headless")))]
+pub use windows::{D2DBackend, D2DGraphics, D2DWindow};
 
 #[derive(Debug, Copy, Clone)]
 pub enum AssetsType {
@@ -49,40 +116,112 @@ pub enum Assets {
 
 impl Assets {
     pub fn as_image(&self) -> Option<Image> {
-        (if let Assets::Image(image) = self {
+        if let Assets::Image(image) = self {
             Some(image.clone())
         } else {
             None
-        })
+        }
     }
 
     pub fn as_file(&self) -> Option<Vec<u8>> {
-        (if let Assets::File(data) = self {
+        if let Assets::File(data) = self {
             Some(data.clone())
         } else {
             None
-        })
+        }
     }
 
     pub fn as_sound(&self) -> Option<Sound> {
-        (if let Assets::Sound(data) = self {
+        if let Assets::Sound(data) = self {
             Some(data.clone())
         } else {
             None
-        })
+        }
     }
 }
 
+/// 鼠标指针样式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorIcon {
+    Default,
+    /// 手形，用于可点击的按钮
+    Pointer,
+    Crosshair,
+    Text,
+    Wait,
+    Progress,
+    Help,
+    Move,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    /// 左右调整大小
+    EwResize,
+    /// 上下调整大小
+    NsResize,
+}
+
+/// Window方法产生的窗口操作，桌面后端在事件循环中执行
+#[cfg(not(any(target_arch = "asmjs", target_arch = "wasm32")))]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum WindowCommand {
+    Title(String),
+    Fullscreen(bool),
+    Size(f64, f64),
+    CursorVisible(bool),
+    Cursor(CursorIcon),
+}
+
 pub trait Window {
     fn set_update_rate(&mut self, ups: u64);
+    fn set_title(&mut self, title: &str);
+    /// 全屏或者退出全屏，Web上需要在用户点击等事件中调用才有效
+    fn set_fullscreen(&mut self, fullscreen: bool);
+    /// 修改窗口大小，Web上忽略
+    fn set_size(&mut self, width: f64, height: f64);
+    fn set_cursor_visible(&mut self, visible: bool);
+    fn set_cursor_icon(&mut self, icon: CursorIcon);
+    /// 退出游戏，Web上停止更新和绘制
+    fn quit(&mut self);
     /// 从文件加载资源
     fn load_assets(&mut self, assets: &[(&str, AssetsType)]);
-    /// 加载RGBA图片数据
-    fn load_image(&mut self, width: u32, height: u32, key: &str, data: Vec<u8>);
-    /// 加载BGRA图片数据
-    fn load_image_bgra(&mut self, width: u32, height: u32, key: &str, data: Vec<u8>);
+    /// 将ImageData上传为Image，完成后通过on_assets_load返回
+    fn load_image_data(&mut self, key: &str, image: ImageData);
     /// 渲染并加载SVG
     fn load_svg(&mut self, key: &str, svg: String);
+    /// 创建离屏画布，画布初始为透明，采样方式和Settings::sampling相同
+    fn create_canvas(&self, width: f64, height: f64) -> Canvas;
+
+    /// 设置游戏时钟的速度，1.0为正常速度，小于1.0为慢动作
+    ///
+    /// 影响update的调用频率和AnimationTimer
+    fn set_time_scale(&mut self, scale: f64) {
+        timing::set_time_scale(scale);
+    }
+
+    fn time_scale(&self) -> f64 {
+        timing::time_scale()
+    }
+
+    /// 暂停游戏时钟，暂停期间不调用update，动画停止，仍然会调用draw和event
+    fn pause(&mut self) {
+        timing::set_paused(true);
+    }
+
+    /// 恢复游戏时钟
+    fn resume(&mut self) {
+        timing::set_paused(false);
+    }
+
+    fn is_paused(&self) -> bool {
+        timing::is_paused()
+    }
+    /// 屏幕缩放比例(物理像素/逻辑像素)
+    ///
+    /// 游戏坐标和窗口大小都使用逻辑像素，后端按照物理像素绘制
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
 }
 
 pub trait Graphics {
@@ -97,18 +236,32 @@ pub trait Graphics {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// g.draw_image(&image, Some([0, 0, 100, 100]), Some([0, 0, 100, 100])).expect("error!");
     /// ```
     fn draw_image(
         &mut self,
-        transform: Option<Transform>,
+        transform: Option<Matrix2D>,
         image: &Image,
         src: Option<[f64; 4]>,
         dest: Option<[f64; 4]>,
+    ) {
+        self.draw_image_with(transform, image, src, dest, &DrawOptions::default());
+    }
+
+    /// 使用透明度、染色和混合方式绘制图片，其余参数和draw_image相同
+    ///
+    /// 各个后端对染色和混合方式的支持见DrawOptions
+    fn draw_image_with(
+        &mut self,
+        transform: Option<Matrix2D>,
+        image: &Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: &DrawOptions,
     );
 
-    fn draw_image_at(&mut self, transform: Option<Transform>, image: &Image, x: f64, y: f64) {
+    fn draw_image_at(&mut self, transform: Option<Matrix2D>, image: &Image, x: f64, y: f64) {
         self.draw_image(
             transform,
             image,
@@ -128,10 +281,269 @@ pub trait Graphics {
     /// * `font_size` 字体 单位pt
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// g.draw_text("Hello!", 0., 20., &[255, 0, 0, 255], 16).expect("text draw failed.");
     /// ```
     fn draw_text(&mut self, cotnent: &str, x: f64, y: f64, color: &[u8; 4], font_size: u32);
+
+    /// 用非零规则填充多边形
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]);
+    /// 描边折线，closed为true时连接首尾两点
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    );
+    /// 填充路径，未闭合的子路径自动闭合
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule);
+    /// 描边路径
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle);
+    /// 使用画刷填充多边形，画刷可以是渐变或者图案
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]);
+    /// 使用画刷填充路径
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule);
+
+    /// 绘制线段
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let style = StrokeStyle::new(3.).with_cap(LineCap::Round);
+    /// g.draw_line(&[255, 0, 0, 255], ship.x, ship.y, target.x, target.y, &style);
+    /// ```
+    fn draw_line(
+        &mut self,
+        color: &[u8; 4],
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        style: &StrokeStyle,
+    ) {
+        self.stroke_polyline(color, &[[x1, y1], [x2, y2]], false, style);
+    }
+    /// 描边多边形
+    fn stroke_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]], style: &StrokeStyle) {
+        self.stroke_polyline(color, points, true, style);
+    }
+    /// 描边矩形
+    fn stroke_rect(
+        &mut self,
+        color: &[u8; 4],
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        style: &StrokeStyle,
+    ) {
+        let points = [
+            [x, y],
+            [x + width, y],
+            [x + width, y + height],
+            [x, y + height],
+        ];
+        self.stroke_polyline(color, &points, true, style);
+    }
+    /// 使用画刷填充矩形
+    fn fill_rect_with(&mut self, brush: &Brush, x: f64, y: f64, width: f64, height: f64) {
+        match brush {
+            Brush::Solid(color) => self.fill_rect(color, x, y, width, height),
+            _ => {
+                let points = [
+                    [x, y],
+                    [x + width, y],
+                    [x + width, y + height],
+                    [x, y + height],
+                ];
+                self.fill_polygon_with(brush, &points);
+            }
+        }
+    }
+    /// 填充圆形
+    fn fill_circle(&mut self, color: &[u8; 4], x: f64, y: f64, radius: f64) {
+        self.fill_ellipse(color, x, y, radius, radius);
+    }
+    /// 描边圆形
+    fn stroke_circle(&mut self, color: &[u8; 4], x: f64, y: f64, radius: f64, style: &StrokeStyle) {
+        self.stroke_ellipse(color, x, y, radius, radius, style);
+    }
+    /// 填充椭圆，(x, y)为中心
+    fn fill_ellipse(&mut self, color: &[u8; 4], x: f64, y: f64, radius_x: f64, radius_y: f64) {
+        let scale = shape::transform_scale(&self.transform());
+        let points = shape::arc_points([x, y], [radius_x, radius_y], 0.0, 2.0 * PI, scale);
+        self.fill_polygon(color, &points);
+    }
+    /// 使用画刷填充圆形
+    fn fill_circle_with(&mut self, brush: &Brush, x: f64, y: f64, radius: f64) {
+        self.fill_ellipse_with(brush, x, y, radius, radius);
+    }
+    /// 使用画刷填充椭圆
+    fn fill_ellipse_with(&mut self, brush: &Brush, x: f64, y: f64, radius_x: f64, radius_y: f64) {
+        let scale = shape::transform_scale(&self.transform());
+        let points = shape::arc_points([x, y], [radius_x, radius_y], 0.0, 2.0 * PI, scale);
+        self.fill_polygon_with(brush, &points);
+    }
+    /// 描边椭圆
+    fn stroke_ellipse(
+        &mut self,
+        color: &[u8; 4],
+        x: f64,
+        y: f64,
+        radius_x: f64,
+        radius_y: f64,
+        style: &StrokeStyle,
+    ) {
+        let scale = shape::transform_scale(&self.transform());
+        let mut points = shape::arc_points([x, y], [radius_x, radius_y], 0.0, 2.0 * PI, scale);
+        //首尾两点重合
+        points.pop();
+        self.stroke_polyline(color, &points, true, style);
+    }
+    /// 描边圆弧，从start到end(弧度，0为x轴正方向，y轴向下时顺时针)
+    #[allow(clippy::too_many_arguments)]
+    fn stroke_arc(
+        &mut self,
+        color: &[u8; 4],
+        x: f64,
+        y: f64,
+        radius: f64,
+        start: f64,
+        end: f64,
+        style: &StrokeStyle,
+    ) {
+        let scale = shape::transform_scale(&self.transform());
+        let points = shape::arc_points([x, y], [radius, radius], start, end, scale);
+        self.stroke_polyline(color, &points, false, style);
+    }
+    /// 填充扇形
+    fn fill_arc(&mut self, color: &[u8; 4], x: f64, y: f64, radius: f64, start: f64, end: f64) {
+        let scale = shape::transform_scale(&self.transform());
+        let mut points = shape::arc_points([x, y], [radius, radius], start, end, scale);
+        points.push([x, y]);
+        self.fill_polygon(color, &points);
+    }
+    /// 填充圆角矩形
+    fn fill_rounded_rect(
+        &mut self,
+        color: &[u8; 4],
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        radius: f64,
+    ) {
+        let scale = shape::transform_scale(&self.transform());
+        let points = shape::rounded_rect_points([x, y, width, height], radius, scale);
+        self.fill_polygon(color, &points);
+    }
+    /// 使用画刷填充圆角矩形，例如血条
+    fn fill_rounded_rect_with(
+        &mut self,
+        brush: &Brush,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        radius: f64,
+    ) {
+        let scale = shape::transform_scale(&self.transform());
+        let points = shape::rounded_rect_points([x, y, width, height], radius, scale);
+        self.fill_polygon_with(brush, &points);
+    }
+    /// 描边圆角矩形
+    #[allow(clippy::too_many_arguments)]
+    fn stroke_rounded_rect(
+        &mut self,
+        color: &[u8; 4],
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        radius: f64,
+        style: &StrokeStyle,
+    ) {
+        let scale = shape::transform_scale(&self.transform());
+        let points = shape::rounded_rect_points([x, y, width, height], radius, scale);
+        self.stroke_polyline(color, &points, true, style);
+    }
+
+    /// 保存当前的绘图状态(变换)，和restore成对使用
+    fn save(&mut self);
+    /// 恢复到上一次save时的绘图状态
+    fn restore(&mut self);
+    /// 当前的变换，作用于之后所有的绘图调用
+    fn transform(&self) -> Matrix2D;
+    /// 替换当前的变换
+    fn set_transform(&mut self, transform: Matrix2D);
+
+    /// 裁剪之后的绘图，只显示rect([x, y, w, h]，使用当前的变换)以内的部分
+    ///
+    /// 多次调用时裁剪区域取交集，和save/restore一样需要和pop_clip成对调用，并且正确嵌套
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// //左右分屏
+    /// g.push_clip([0., 0., 400., 600.]);
+    /// draw_world(g, &player1_camera);
+    /// g.pop_clip();
+    /// g.push_clip([400., 0., 400., 600.]);
+    /// g.translate(400., 0.);
+    /// draw_world(g, &player2_camera);
+    /// g.pop_clip();
+    /// ```
+    fn push_clip(&mut self, rect: [f64; 4]);
+    /// 取消最近一次push_clip
+    fn pop_clip(&mut self);
+
+    /// 图片缩放时的采样方式，初始值为Settings::sampling，绘制图片时可以用DrawOptions单独指定
+    fn set_sampling(&mut self, sampling: Sampling);
+    fn sampling(&self) -> Sampling;
+
+    /// 平移之后的绘图
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// g.save();
+    /// g.translate(ship.x, ship.y);
+    /// g.rotate(ship.angle);
+    /// g.draw_image_at(None, &ship_image, -16., -16.);
+    /// g.rotate(turret_angle);
+    /// g.draw_image_at(None, &turret_image, -4., -12.);
+    /// g.restore();
+    /// ```
+    fn translate(&mut self, x: f64, y: f64) {
+        let transform = self.transform().translate(x, y);
+        self.set_transform(transform);
+    }
+    /// 旋转之后的绘图(弧度)
+    fn rotate(&mut self, angle: f64) {
+        let transform = self.transform().rotate(angle);
+        self.set_transform(transform);
+    }
+    /// 缩放之后的绘图
+    fn scale(&mut self, x: f64, y: f64) {
+        let transform = self.transform().scale(x, y);
+        self.set_transform(transform);
+    }
+
+    /// 读取当前已绘制的画面
+    ///
+    /// 在draw的最后调用可以得到完整的一帧，不支持读取画面的后端返回None
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if let Some(frame) = g.capture_frame() {
+    ///     frame.save_png("screenshot.png").unwrap();
+    /// }
+    /// ```
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -140,15 +552,18 @@ pub enum Event {
     Click(f64, f64),
     KeyDown(String),
     KeyUp(String),
+    /// 屏幕缩放比例改变(比如窗口移动到另一个显示器)
+    ScaleFactorChanged(f64),
 }
 
 pub trait State: 'static {
     fn new(window: &mut impl Window) -> Self;
-    fn update(&mut self, _window: &mut impl Window) {}
+    /// 按照固定的时间步长调用，time.dt固定为 1/ups 秒
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {}
     fn event(&mut self, _event: Event, _window: &mut impl Window) {}
-    fn draw(&mut self, graphics: &mut impl Graphics, window: &mut impl Window);
-    ///
-    ///
+    /// 绘制画面，time.alpha可用于在两次update之间插值
+    fn draw(&mut self, graphics: &mut impl Graphics, window: &mut impl Window, time: FrameTime);
+    /// 资源加载完成(或失败)时调用
     fn on_assets_load(
         &mut self,
         path: &str,
@@ -156,6 +571,20 @@ pub trait State: 'static {
         assets: std::io::Result<Assets>,
         window: &mut impl Window,
     );
+    /// 窗口大小改变时调用，width、height为新的窗口大小
+    fn on_resize(&mut self, _width: f64, _height: f64, _window: &mut impl Window) {}
+    /// 窗口获得(true)或失去(false)焦点时调用
+    fn on_focus_changed(&mut self, _focused: bool, _window: &mut impl Window) {}
+    /// 游戏被挂起时调用(比如Web页面切换到后台)
+    fn on_suspend(&mut self, _window: &mut impl Window) {}
+    /// 游戏从挂起状态恢复时调用
+    fn on_resume(&mut self, _window: &mut impl Window) {}
+    /// 用户关闭窗口时调用，返回false可以阻止关闭(比如先询问是否保存)
+    ///
+    /// Web上返回false时浏览器会弹出确认离开的对话框
+    fn on_exit(&mut self, _window: &mut impl Window) -> bool {
+        true
+    }
     #[cfg(any(target_arch = "asmjs", target_arch = "wasm32"))]
     fn handle_error(&mut self, error: String) {
         console!(error, error);
@@ -166,7 +595,7 @@ pub trait State: 'static {
     }
 }
 
-//计时器
+//计时器，使用游戏时间(game_timestamp)
 #[derive(Clone)]
 pub struct AnimationTimer {
     frame_time: f64,
@@ -177,7 +606,7 @@ impl AnimationTimer {
     pub fn new(fps: f64) -> AnimationTimer {
         AnimationTimer {
             frame_time: 1000.0 / fps,
-            next_time: current_timestamp(),
+            next_time: game_timestamp(),
         }
     }
 
@@ -186,11 +615,11 @@ impl AnimationTimer {
     }
 
     pub fn reset(&mut self) {
-        self.next_time = current_timestamp();
+        self.next_time = game_timestamp();
     }
 
     pub fn ready_for_next_frame(&mut self) -> bool {
-        let now = current_timestamp();
+        let now = game_timestamp();
         if now >= self.next_time {
             //更新时间
             self.next_time += self.frame_time;
@@ -211,11 +640,19 @@ impl SubImage {
         SubImage { image, region }
     }
 
-    pub fn draw(&self, transform: Option<Transform>, g: &mut impl Graphics, dest: [f64; 4]) {
+    pub fn draw(&self, transform: Option<Matrix2D>, g: &mut impl Graphics, dest: [f64; 4]) {
         g.draw_image(transform, &self.image, Some(self.region), Some(dest));
     }
 }
 
+/// 角色的朝向，图片默认朝右，朝左时水平翻转绘制
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Facing {
+    #[default]
+    Right,
+    Left,
+}
+
 #[derive(Clone)]
 pub struct Animation {
     timer: AnimationTimer,
@@ -224,7 +661,7 @@ pub struct Animation {
     current: i32,
     repeat: bool,
     active: bool,
-    end: bool, //current == frames.len()
+    facing: Facing,
     pub position: Option<[f64; 4]>,
 }
 
@@ -237,7 +674,7 @@ impl Animation {
             current: -1,
             repeat: false,
             active: false,
-            end: false,
+            facing: Facing::Right,
             position: None,
         }
     }
@@ -249,7 +686,7 @@ impl Animation {
     }
 
     pub fn frame_width(&self) -> f64 {
-        if self.frames.len() == 0 {
+        if self.frames.is_empty() {
             0.0
         } else {
             self.frames[0][2]
@@ -257,7 +694,7 @@ impl Animation {
     }
 
     pub fn frame_height(&self) -> f64 {
-        if self.frames.len() == 0 {
+        if self.frames.is_empty() {
             0.0
         } else {
             self.frames[0][3]
@@ -276,6 +713,14 @@ impl Animation {
         self.repeat
     }
 
+    pub fn set_facing(&mut self, facing: Facing) {
+        self.facing = facing;
+    }
+
+    pub fn facing(&self) -> Facing {
+        self.facing
+    }
+
     pub fn start(&mut self) {
         self.active = true;
         self.current = -1;
@@ -293,23 +738,34 @@ impl Animation {
     /// Tick the animation forward by one step
     pub fn update(&mut self) -> bool {
         let mut jump = false;
-        if self.active {
-            if self.timer.ready_for_next_frame() {
-                self.current += 1;
-                if self.current == self.frames.len() as i32 {
-                    if self.repeat {
-                        self.current = 0;
-                    } else {
-                        self.active = false;
-                    }
+        if self.active && self.timer.ready_for_next_frame() {
+            self.current += 1;
+            if self.current == self.frames.len() as i32 {
+                if self.repeat {
+                    self.current = 0;
+                } else {
+                    self.active = false;
                 }
-                jump = true;
             }
+            jump = true;
         }
         jump
     }
 
-    pub fn draw(&self, transform: Option<Transform>, g: &mut impl Graphics, dest: [f64; 4]) {
+    pub fn draw(&self, transform: Option<Matrix2D>, g: &mut impl Graphics, dest: [f64; 4]) {
+        self.draw_with(transform, g, dest, &DrawOptions::default());
+    }
+
+    /// 使用指定的选项绘制当前帧，朝左时在options的基础上再水平翻转
+    pub fn draw_with(
+        &self,
+        transform: Option<Matrix2D>,
+        g: &mut impl Graphics,
+        dest: [f64; 4],
+        options: &DrawOptions,
+    ) {
+        let mut options = *options;
+        options.flip_x ^= self.facing == Facing::Left;
         let mut current = 0;
         if self.current > 0 {
             current = if self.current == self.frames.len() as i32 {
@@ -318,11 +774,12 @@ impl Animation {
                 self.current
             };
         }
-        g.draw_image(
+        g.draw_image_with(
             transform,
             &self.image,
             Some(self.frames[current as usize]),
             Some(dest),
+            &options,
         );
     }
 }
@@ -338,10 +795,10 @@ pub struct Rect {
 impl Rect {
     pub fn new(left: f64, top: f64, right: f64, bottom: f64) -> Rect {
         Rect {
-            left: left,
-            top: top,
-            right: right,
-            bottom: bottom,
+            left,
+            top,
+            right,
+            bottom,
         }
     }
 
@@ -520,14 +977,18 @@ pub struct Settings {
     pub fullscreen: bool,
     /// How many times is the update method called per second
     pub ups: u64,
+    /// 卡顿之后一次最多补偿的update次数，超出的时间会被丢弃
+    pub max_updates_per_frame: u32,
     pub icon_path: Option<&'static str>, // TODO: statiC?
     /// 背景色[r,g,b,a]
     pub background_color: Option<[u8; 4]>,
     pub window_size: Option<(f64, f64)>,
-    /// 居中绘图
-    pub draw_center: bool,
-    /// 自动缩放
-    pub auto_scale: bool,
+    /// 画面缩放到窗口的方式
+    pub scale_mode: ScaleMode,
+    /// 窗口中画面以外区域的颜色[r,g,b,a]，默认使用背景色
+    pub letterbox_color: Option<[u8; 4]>,
+    /// 图片缩放时的采样方式，像素风格的游戏使用Sampling::Nearest
+    pub sampling: Sampling,
     /// 显示更新频率 UPS/FPS
     pub show_ups_fps: bool,
 }
@@ -540,10 +1001,12 @@ impl Default for Settings {
             max_size: None,
             fullscreen: false,
             ups: 60,
+            max_updates_per_frame: 5,
             icon_path: None,
             background_color: None,
-            draw_center: true,
-            auto_scale: false,
+            scale_mode: ScaleMode::None,
+            letterbox_color: None,
+            sampling: Sampling::Linear,
             window_size: None,
             show_ups_fps: false,
         }
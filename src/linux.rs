@@ -0,0 +1,386 @@
+use super::desktop::{apply_command, build_window, key_event, mouse_event};
+use super::headless::{HeadlessGraphics, HeadlessWindow};
+use super::{Backend, Event, Graphics, Settings, State, Viewport};
+use image::RgbaImage;
+use std::os::raw::{c_char, c_uint, c_ulong};
+use std::ptr;
+#[cfg(feature = "audio")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(feature = "audio"))]
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant};
+use winit::dpi::LogicalSize;
+use winit::os::unix::x11::{ffi, XConnection};
+use winit::os::unix::{EventsLoopExt, WindowExt};
+
+/// Linux桌面后端，使用winit创建X11窗口，画面由CPU按照物理像素绘制之后通过XPutImage显示
+///
+/// 只支持X11，Wayland桌面需要XWayland，连接不到X11时panic。
+/// 声音需要启用audio特性(使用rodio，依赖ALSA)，否则第一次播放时提示一次
+pub struct LinuxBackend;
+
+impl Backend for LinuxBackend {
+    type Image = RgbaImage;
+    type Sound = Vec<u8>;
+    type Window = HeadlessWindow;
+    type Graphics = HeadlessGraphics;
+
+    fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+        run::<S>(title, width, height, settings);
+    }
+
+    fn play_sound(sound: &Vec<u8>) {
+        play_sound(sound);
+    }
+}
+
+/// 启动线程播放声音
+#[cfg(feature = "audio")]
+fn play_sound(sound: &[u8]) {
+    let buffer = sound.to_vec();
+    thread::spawn(move || {
+        let device = match rodio::default_output_device() {
+            Some(device) => device,
+            None => {
+                eprintln!("no default output device.");
+                return;
+            }
+        };
+        let sink = rodio::Sink::new(&device);
+        match rodio::Decoder::new(std::io::Cursor::new(buffer)) {
+            Ok(decoder) => {
+                sink.append(decoder);
+                sink.sleep_until_end();
+            }
+            Err(err) => eprintln!("{:?}", err),
+        }
+    });
+}
+
+#[cfg(not(feature = "audio"))]
+fn play_sound(_sound: &[u8]) {
+    warn_no_audio();
+}
+
+//背景音乐播放中，由音频线程定期检查
+#[cfg(feature = "audio")]
+static PLAYING_MUSIC: AtomicBool = AtomicBool::new(false);
+
+/// 播放static目录下的音乐，repeat为true时循环播放直到stop_music
+#[cfg(feature = "audio")]
+pub fn play_music(file: &str, repeat: bool) {
+    use rodio::Source;
+    let device = match rodio::default_output_device() {
+        Some(device) => device,
+        None => {
+            eprintln!("no default output device.");
+            return;
+        }
+    };
+    let path = "./static/".to_owned() + file;
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("{}: {:?}", path, err);
+            return;
+        }
+    };
+    let source = match rodio::Decoder::new(std::io::BufReader::new(file)) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {:?}", path, err);
+            return;
+        }
+    };
+    //stop_music之后音频线程停止播放
+    fn stop<S>(src: &mut rodio::source::Stoppable<S>) {
+        if !PLAYING_MUSIC.load(Ordering::SeqCst) {
+            src.stop();
+        }
+    }
+    PLAYING_MUSIC.store(true, Ordering::SeqCst);
+    if repeat {
+        let source = source
+            .repeat_infinite()
+            .convert_samples()
+            .stoppable()
+            .periodic_access(Duration::from_millis(100), stop);
+        rodio::play_raw(&device, source);
+    } else {
+        let source = source
+            .convert_samples()
+            .stoppable()
+            .periodic_access(Duration::from_millis(100), stop);
+        rodio::play_raw(&device, source);
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+pub fn play_music(_file: &str, _repeat: bool) {
+    warn_no_audio();
+}
+
+#[cfg(feature = "audio")]
+pub fn stop_music() {
+    PLAYING_MUSIC.store(false, Ordering::SeqCst);
+}
+
+#[cfg(not(feature = "audio"))]
+pub fn stop_music() {}
+
+/// 没有启用audio特性时，第一次播放声音提示一次
+#[cfg(not(feature = "audio"))]
+fn warn_no_audio() {
+    static WARNING: Once = Once::new();
+    WARNING.call_once(|| {
+        eprintln!("mengine: 没有启用audio特性，Linux后端不播放声音");
+    });
+}
+
+/// 将帧缓冲显示到X11窗口
+struct XPresenter {
+    xconn: Arc<XConnection>,
+    window: c_ulong,
+    gc: ffi::GC,
+    visual: *mut ffi::Visual,
+    depth: c_uint,
+    //窗口大小的0x00RRGGBB像素
+    pixels: Vec<u32>,
+}
+
+impl XPresenter {
+    fn new(window: &winit::Window) -> Option<XPresenter> {
+        let xconn = window.get_xlib_xconnection()?;
+        let xwindow = window.get_xlib_window()?;
+        let screen = window.get_xlib_screen_id()?;
+        let (gc, visual, depth) = unsafe {
+            (
+                (xconn.xlib.XDefaultGC)(xconn.display, screen),
+                (xconn.xlib.XDefaultVisual)(xconn.display, screen),
+                (xconn.xlib.XDefaultDepth)(xconn.display, screen) as c_uint,
+            )
+        };
+        Some(XPresenter {
+            xconn,
+            window: xwindow,
+            gc,
+            visual,
+            depth,
+            pixels: vec![],
+        })
+    }
+
+    /// 将已经按物理像素绘制的frame复制到窗口的offset位置，其余部分填充letterbox颜色
+    fn present(
+        &mut self,
+        frame: &RgbaImage,
+        size: (u32, u32),
+        offset: (i64, i64),
+        letterbox_color: &[u8; 4],
+    ) {
+        let (width, height) = size;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let rgb = |c: &[u8]| (c[0] as u32) << 16 | (c[1] as u32) << 8 | c[2] as u32;
+        let letterbox = rgb(letterbox_color);
+        self.pixels.clear();
+        self.pixels.resize((width * height) as usize, letterbox);
+        for y in 0..height {
+            let sy = y as i64 - offset.1;
+            if sy < 0 || sy >= frame.height() as i64 {
+                continue;
+            }
+            let row = &mut self.pixels[(y * width) as usize..((y + 1) * width) as usize];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let sx = x as i64 - offset.0;
+                if sx < 0 || sx >= frame.width() as i64 {
+                    continue;
+                }
+                *pixel = rgb(&frame.get_pixel(sx as u32, sy as u32).0);
+            }
+        }
+
+        let xlib = &self.xconn.xlib;
+        let display = self.xconn.display;
+        unsafe {
+            let image = (xlib.XCreateImage)(
+                display,
+                self.visual,
+                self.depth,
+                ffi::ZPixmap,
+                0,
+                self.pixels.as_mut_ptr() as *mut c_char,
+                width,
+                height,
+                32,
+                0,
+            );
+            if image.is_null() {
+                return;
+            }
+            (xlib.XPutImage)(
+                display,
+                self.window,
+                self.gc,
+                image,
+                0,
+                0,
+                0,
+                0,
+                width,
+                height,
+            );
+            //像素数据由pixels管理，不能让XDestroyImage释放
+            (*image).data = ptr::null_mut();
+            (xlib.XDestroyImage)(image);
+            (xlib.XFlush)(display);
+        }
+    }
+}
+
+fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+    //画面通过XPutImage显示，只能使用X11(Wayland下需要XWayland)
+    let mut events_loop = match winit::EventsLoop::new_x11() {
+        Ok(events_loop) => events_loop,
+        Err(err) => panic!("无法连接X11(Wayland桌面需要XWayland): {:?}", err),
+    };
+
+    let initial_window_size = settings.window_size.unwrap_or((width, height));
+    let window = build_window(title, initial_window_size, &settings, &events_loop);
+
+    let mut presenter = XPresenter::new(&window).expect("无法获取X11窗口");
+
+    //窗口的物理像素大小
+    let physical_size = |size: LogicalSize, hidpi_factor: f64| {
+        let size = size.to_physical(hidpi_factor);
+        (size.width as u32, size.height as u32)
+    };
+    let mut hidpi_factor = window.get_hidpi_factor();
+    let mut logical_size = window
+        .get_inner_size()
+        .unwrap_or_else(|| LogicalSize::new(initial_window_size.0, initial_window_size.1));
+    let mut window_size = physical_size(logical_size, hidpi_factor);
+
+    let mut graphics = HeadlessGraphics::new(width as u32, height as u32);
+    graphics.set_sampling(settings.sampling);
+    let background_color = settings.background_color.unwrap_or([0, 0, 0, 255]);
+    let letterbox_color = settings.letterbox_color.unwrap_or(background_color);
+    let frame_delay = Duration::from_micros(1000 * 1000 / 60);
+
+    let mut mouse_pos = [0.0; 2];
+    let game_size = (width, height);
+    let mut viewport = Viewport::new(settings.scale_mode, game_size, game_size, hidpi_factor);
+
+    let mut game_window = HeadlessWindow::new(&settings);
+    game_window.set_scale_factor(hidpi_factor);
+    let mut game = S::new(&mut game_window);
+    let timer = Instant::now();
+    let mut next_draw_time = timer.elapsed();
+    let mut next_log_time = timer.elapsed();
+    let next_log_delay = Duration::from_millis(1000);
+    let (mut fps, mut ups, mut ups_count, mut fps_count) = (0, 0, 0, 0);
+    let mut running = true;
+    while running && !game_window.is_quit() {
+        game_window.dispatch_assets(&mut game);
+        ups_count += game_window.run_updates(&mut game, timer.elapsed().as_secs_f64() * 1000.0);
+        for command in game_window.take_commands() {
+            apply_command(&window, command);
+        }
+        if timer.elapsed() >= next_draw_time {
+            next_draw_time += frame_delay;
+            fps_count += 1;
+
+            viewport = Viewport::new(
+                settings.scale_mode,
+                game_size,
+                (window_size.0 as f64, window_size.1 as f64),
+                hidpi_factor,
+            );
+            //按照物理像素绘制
+            graphics.resize(
+                (width * viewport.scale_x).ceil() as u32,
+                (height * viewport.scale_y).ceil() as u32,
+                (viewport.scale_x, viewport.scale_y),
+            );
+
+            graphics.clear(&background_color);
+            let time = game_window.frame_time();
+            game.draw(&mut graphics, &mut game_window, time);
+            //显示UPS/FPS
+            if settings.show_ups_fps {
+                graphics.reset_state();
+                graphics.draw_text(
+                    &format!("UPS/FPS:{}/{}", ups, fps),
+                    20.0,
+                    height - 30.,
+                    &[255, 255, 0, 255],
+                    10,
+                );
+            }
+            presenter.present(
+                graphics.frame(),
+                window_size,
+                (viewport.x.round() as i64, viewport.y.round() as i64),
+                &letterbox_color,
+            );
+        }
+        if timer.elapsed() > next_log_time {
+            next_log_time += next_log_delay;
+            ups = ups_count;
+            fps = fps_count;
+            ups_count = 0;
+            fps_count = 0;
+        }
+
+        events_loop.poll_events(|event| match event {
+            winit::Event::WindowEvent { event, .. } => {
+                match event {
+                    winit::WindowEvent::Resized(size) => {
+                        logical_size = size;
+                        window_size = physical_size(size, hidpi_factor);
+                        game.on_resize(size.width, size.height, &mut game_window);
+                    }
+                    winit::WindowEvent::HiDpiFactorChanged(factor) => {
+                        hidpi_factor = factor;
+                        window_size = physical_size(logical_size, hidpi_factor);
+                        game_window.set_scale_factor(factor);
+                        game.event(Event::ScaleFactorChanged(factor), &mut game_window);
+                    }
+                    winit::WindowEvent::CloseRequested => {
+                        if game.on_exit(&mut game_window) {
+                            running = false;
+                        }
+                    }
+                    winit::WindowEvent::Focused(focused) => {
+                        game.on_focus_changed(focused, &mut game_window);
+                    }
+                    winit::WindowEvent::KeyboardInput { input, .. } => {
+                        if let Some(event) = key_event(&input) {
+                            game.event(event, &mut game_window);
+                        }
+                    }
+                    event => {
+                        //鼠标位置是逻辑坐标，转换为物理像素之后再转换为游戏坐标
+                        if let Some(event) = mouse_event(&event, &mut mouse_pos, |x, y| {
+                            viewport.to_game(x * hidpi_factor, y * hidpi_factor)
+                        }) {
+                            game.event(event, &mut game_window);
+                        }
+                    }
+                }
+            }
+            winit::Event::Suspended(true) => game.on_suspend(&mut game_window),
+            winit::Event::Suspended(false) => game.on_resume(&mut game_window),
+            _ => (),
+        });
+
+        let next = (timer.elapsed() + game_window.next_update_delay()).min(next_draw_time);
+        let now = timer.elapsed();
+        if next > now {
+            thread::sleep((next - now).min(Duration::from_millis(5)));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+/// 2D仿射变换矩阵，和canvas的setTransform(a, b, c, d, e, f)含义相同
+///
+/// ```text
+/// x' = a * x + c * y + e
+/// y' = b * x + d * y + f
+/// ```
+///
+/// # Example
+///
+/// ```ignore
+/// //以(190, 190)为中心旋转，和canvas中 translate(190, 190); rotate(angle); 相同
+/// let transform = Matrix2D::translation(190., 190.).rotate(angle);
+/// g.draw_image_at(Some(transform), &image, -r, -r);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix2D {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Matrix2D {
+    fn default() -> Matrix2D {
+        Matrix2D::IDENTITY
+    }
+}
+
+impl Matrix2D {
+    pub const IDENTITY: Matrix2D = Matrix2D::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    pub const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Matrix2D {
+        Matrix2D { a, b, c, d, e, f }
+    }
+
+    /// 平移
+    pub fn translation(x: f64, y: f64) -> Matrix2D {
+        Matrix2D::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    /// 绕原点旋转angle弧度(y轴向下时为顺时针)
+    pub fn rotation(angle: f64) -> Matrix2D {
+        let (sin, cos) = angle.sin_cos();
+        Matrix2D::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// 绕(x, y)旋转angle弧度
+    pub fn rotation_around(angle: f64, x: f64, y: f64) -> Matrix2D {
+        Matrix2D::translation(-x, -y)
+            .then(&Matrix2D::rotation(angle))
+            .then(&Matrix2D::translation(x, y))
+    }
+
+    /// 缩放
+    pub fn scaling(x: f64, y: f64) -> Matrix2D {
+        Matrix2D::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    /// 斜切，x、y为x轴和y轴方向倾斜的弧度
+    pub fn skewing(x: f64, y: f64) -> Matrix2D {
+        Matrix2D::new(1.0, y.tan(), x.tan(), 1.0, 0.0, 0.0)
+    }
+
+    /// 组合变换：先应用self，再应用next
+    pub fn then(&self, next: &Matrix2D) -> Matrix2D {
+        Matrix2D::new(
+            self.a * next.a + self.b * next.c,
+            self.a * next.b + self.b * next.d,
+            self.c * next.a + self.d * next.c,
+            self.c * next.b + self.d * next.d,
+            self.e * next.a + self.f * next.c + next.e,
+            self.e * next.b + self.f * next.d + next.f,
+        )
+    }
+
+    /// 在当前变换的坐标系中平移(和canvas的translate相同，新的变换先作用于坐标)
+    pub fn translate(&self, x: f64, y: f64) -> Matrix2D {
+        Matrix2D::translation(x, y).then(self)
+    }
+
+    /// 在当前变换的坐标系中旋转
+    pub fn rotate(&self, angle: f64) -> Matrix2D {
+        Matrix2D::rotation(angle).then(self)
+    }
+
+    /// 在当前变换的坐标系中缩放
+    pub fn scale(&self, x: f64, y: f64) -> Matrix2D {
+        Matrix2D::scaling(x, y).then(self)
+    }
+
+    /// 在当前变换的坐标系中斜切
+    pub fn skew(&self, x: f64, y: f64) -> Matrix2D {
+        Matrix2D::skewing(x, y).then(self)
+    }
+
+    /// 变换一个点
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// 逆矩阵，不可逆(比如缩放为0)时返回None
+    pub fn invert(&self) -> Option<Matrix2D> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        Some(Matrix2D::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn invert_round_trips_points() {
+        let m = Matrix2D::translation(30., -12.)
+            .rotate(0.7)
+            .scale(2., 0.5)
+            .skew(0.2, 0.);
+        let inv = m.invert().unwrap();
+        for &(x, y) in &[(0., 0.), (5., -3.), (-120., 48.)] {
+            let (tx, ty) = m.apply(x, y);
+            assert_point(inv.apply(tx, ty), (x, y));
+        }
+        assert_point(m.then(&inv).apply(7., 9.), (7., 9.));
+    }
+
+    #[test]
+    fn invert_zero_scale_is_none() {
+        assert_eq!(Matrix2D::scaling(0., 1.).invert(), None);
+        assert_eq!(Matrix2D::scaling(1., 0.).invert(), None);
+        assert_eq!(Matrix2D::scaling(0., 0.).translate(5., 5.).invert(), None);
+    }
+
+    #[test]
+    fn invert_collinear_axes_is_none() {
+        //两个轴平行，所有点都落在同一条直线上
+        assert_eq!(Matrix2D::new(1., 2., 2., 4., 3., 3.).invert(), None);
+        assert_eq!(Matrix2D::new(0., 0., 0., 0., 1., 1.).invert(), None);
+    }
+
+    #[test]
+    fn invert_non_finite_is_none() {
+        assert_eq!(Matrix2D::new(f64::NAN, 0., 0., 1., 0., 0.).invert(), None);
+        assert_eq!(Matrix2D::scaling(f64::INFINITY, 1.).invert(), None);
+        //行列式下溢为0
+        assert_eq!(Matrix2D::scaling(1e-200, 1e-200).invert(), None);
+    }
+
+    #[test]
+    fn invert_identity() {
+        assert_eq!(Matrix2D::IDENTITY.invert(), Some(Matrix2D::IDENTITY));
+    }
+}
@@ -0,0 +1,354 @@
+use std::f64::consts::PI;
+
+/// 填充规则
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillRule {
+    /// 非零环绕规则
+    #[default]
+    NonZero,
+    /// 奇偶规则，重叠奇数次的区域才填充
+    EvenOdd,
+}
+
+/// 路径中的一条命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathCommand {
+    MoveTo([f64; 2]),
+    LineTo([f64; 2]),
+    /// 控制点，终点
+    QuadTo([f64; 2], [f64; 2]),
+    /// 控制点1，控制点2，终点
+    CubicTo([f64; 2], [f64; 2], [f64; 2]),
+    Close,
+}
+
+/// 矢量路径，用法和canvas的路径相同
+///
+/// # Example
+///
+/// ```ignore
+/// let mut path = Path::new();
+/// path.move_to(0., 100.)
+///     .quad_to(50., 0., 100., 100.)
+///     .line_to(50., 150.)
+///     .close();
+/// g.fill_path(&[0, 128, 0, 255], &path, FillRule::NonZero);
+/// g.stroke_path(&[255, 255, 255, 255], &path, &StrokeStyle::new(2.).with_dash(&[6., 4.], 0.));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    commands: Vec<PathCommand>,
+    //当前点和当前子路径的起点
+    current: Option<[f64; 2]>,
+    start: [f64; 2],
+}
+
+impl Path {
+    pub fn new() -> Path {
+        Path::default()
+    }
+
+    pub fn commands(&self) -> &[PathCommand] {
+        &self.commands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// 开始新的子路径
+    pub fn move_to(&mut self, x: f64, y: f64) -> &mut Path {
+        self.commands.push(PathCommand::MoveTo([x, y]));
+        self.current = Some([x, y]);
+        self.start = [x, y];
+        self
+    }
+
+    /// 没有当前点时，和move_to相同
+    pub fn line_to(&mut self, x: f64, y: f64) -> &mut Path {
+        if self.current.is_none() {
+            return self.move_to(x, y);
+        }
+        self.commands.push(PathCommand::LineTo([x, y]));
+        self.current = Some([x, y]);
+        self
+    }
+
+    /// 二次贝塞尔曲线
+    pub fn quad_to(&mut self, cx: f64, cy: f64, x: f64, y: f64) -> &mut Path {
+        if self.current.is_none() {
+            self.move_to(cx, cy);
+        }
+        self.commands.push(PathCommand::QuadTo([cx, cy], [x, y]));
+        self.current = Some([x, y]);
+        self
+    }
+
+    /// 三次贝塞尔曲线
+    #[allow(clippy::too_many_arguments)]
+    pub fn cubic_to(
+        &mut self,
+        c1x: f64,
+        c1y: f64,
+        c2x: f64,
+        c2y: f64,
+        x: f64,
+        y: f64,
+    ) -> &mut Path {
+        if self.current.is_none() {
+            self.move_to(c1x, c1y);
+        }
+        self.commands
+            .push(PathCommand::CubicTo([c1x, c1y], [c2x, c2y], [x, y]));
+        self.current = Some([x, y]);
+        self
+    }
+
+    /// 和canvas的arcTo相同：添加一段与(当前点, (x1, y1))和((x1, y1), (x2, y2))两条直线相切的圆弧
+    pub fn arc_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) -> &mut Path {
+        let p0 = match self.current {
+            Some(p0) => p0,
+            None => return self.move_to(x1, y1),
+        };
+        let v1 = [p0[0] - x1, p0[1] - y1];
+        let v2 = [x2 - x1, y2 - y1];
+        let (len1, len2) = (v1[0].hypot(v1[1]), v2[0].hypot(v2[1]));
+        let cross = v1[0] * v2[1] - v1[1] * v2[0];
+        //重合或者共线时只连接到(x1, y1)
+        if radius <= 0.0 || len1 == 0.0 || len2 == 0.0 || cross.abs() < 1e-9 * len1 * len2 {
+            return self.line_to(x1, y1);
+        }
+        let (u1, u2) = ([v1[0] / len1, v1[1] / len1], [v2[0] / len2, v2[1] / len2]);
+        let angle = (u1[0] * u2[0] + u1[1] * u2[1]).clamp(-1.0, 1.0).acos();
+        let distance = radius / (angle / 2.0).tan();
+        let t1 = [x1 + u1[0] * distance, y1 + u1[1] * distance];
+        let t2 = [x1 + u2[0] * distance, y1 + u2[1] * distance];
+        let bisector = [u1[0] + u2[0], u1[1] + u2[1]];
+        let bisector_len = bisector[0].hypot(bisector[1]);
+        let center_distance = radius / (angle / 2.0).sin();
+        let center = [
+            x1 + bisector[0] / bisector_len * center_distance,
+            y1 + bisector[1] / bisector_len * center_distance,
+        ];
+        let start = (t1[1] - center[1]).atan2(t1[0] - center[0]);
+        let end = (t2[1] - center[1]).atan2(t2[0] - center[0]);
+        let mut sweep = end - start;
+        if sweep > PI {
+            sweep -= 2.0 * PI;
+        } else if sweep < -PI {
+            sweep += 2.0 * PI;
+        }
+        self.line_to(t1[0], t1[1]);
+        self.arc_cubics(center, radius, start, sweep);
+        self
+    }
+
+    /// 闭合当前子路径
+    pub fn close(&mut self) -> &mut Path {
+        if self.current.is_some() {
+            self.commands.push(PathCommand::Close);
+            self.current = Some(self.start);
+        }
+        self
+    }
+
+    /// 用三次贝塞尔曲线近似圆弧，每段不超过90度
+    fn arc_cubics(&mut self, center: [f64; 2], radius: f64, start: f64, sweep: f64) {
+        let segments = (sweep.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+        let step = sweep / segments as f64;
+        let k = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+        let point = |angle: f64| {
+            let (sin, cos) = angle.sin_cos();
+            (
+                [center[0] + radius * cos, center[1] + radius * sin],
+                [-sin, cos],
+            )
+        };
+        for i in 0..segments {
+            let (p0, d0) = point(start + step * i as f64);
+            let (p1, d1) = point(start + step * (i + 1) as f64);
+            self.cubic_to(
+                p0[0] + d0[0] * k,
+                p0[1] + d0[1] * k,
+                p1[0] - d1[0] * k,
+                p1[1] - d1[1] * k,
+                p1[0],
+                p1[1],
+            );
+        }
+    }
+
+    /// 将路径展开为折线，返回每个子路径的点和是否闭合，scale为当前变换的缩放
+    pub(crate) fn flatten(&self, scale: f64) -> Vec<(Vec<[f64; 2]>, bool)> {
+        let mut subpaths = vec![];
+        let mut points: Vec<[f64; 2]> = vec![];
+        let mut start = [0.0; 2];
+        let mut last = [0.0; 2];
+        for command in &self.commands {
+            if let PathCommand::MoveTo(p) = command {
+                if points.len() > 1 {
+                    subpaths.push((points, false));
+                }
+                points = vec![*p];
+                start = *p;
+                last = *p;
+                continue;
+            }
+            //close之后继续绘制时从起点开始新的子路径
+            if points.is_empty() {
+                points.push(start);
+            }
+            match command {
+                PathCommand::LineTo(p) => points.push(*p),
+                PathCommand::QuadTo(c, p) => {
+                    let dd = (last[0] - 2.0 * c[0] + p[0]).hypot(last[1] - 2.0 * c[1] + p[1]);
+                    let n = segment_count(dd * scale / 4.0);
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let mt = 1.0 - t;
+                        points.push([
+                            mt * mt * last[0] + 2.0 * mt * t * c[0] + t * t * p[0],
+                            mt * mt * last[1] + 2.0 * mt * t * c[1] + t * t * p[1],
+                        ]);
+                    }
+                }
+                PathCommand::CubicTo(c1, c2, p) => {
+                    let dd1 = (last[0] - 2.0 * c1[0] + c2[0]).hypot(last[1] - 2.0 * c1[1] + c2[1]);
+                    let dd2 = (c1[0] - 2.0 * c2[0] + p[0]).hypot(c1[1] - 2.0 * c2[1] + p[1]);
+                    let n = segment_count(dd1.max(dd2) * scale * 0.75);
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let mt = 1.0 - t;
+                        let (w0, w1, w2, w3) =
+                            (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                        points.push([
+                            w0 * last[0] + w1 * c1[0] + w2 * c2[0] + w3 * p[0],
+                            w0 * last[1] + w1 * c1[1] + w2 * c2[1] + w3 * p[1],
+                        ]);
+                    }
+                }
+                PathCommand::Close => {
+                    if points.len() > 1 {
+                        subpaths.push((points, true));
+                    }
+                    points = vec![];
+                    last = start;
+                    continue;
+                }
+                PathCommand::MoveTo(_) => unreachable!(),
+            }
+            last = points[points.len() - 1];
+        }
+        if points.len() > 1 {
+            subpaths.push((points, false));
+        }
+        subpaths
+    }
+}
+
+/// 曲线展开的分段数，error为只分一段时的误差(像素)，误差不超过0.1像素
+fn segment_count(error: f64) -> usize {
+    ((error / 0.1).sqrt().ceil() as usize).clamp(1, 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arc_path(x2: f64, y2: f64, radius: f64) -> Path {
+        let mut path = Path::new();
+        path.move_to(0., 0.).arc_to(10., 0., x2, y2, radius);
+        path
+    }
+
+    #[test]
+    fn collinear_arc_to_is_line() {
+        //继续向前、折返，都只连接到(x1, y1)
+        for &(x2, y2) in &[(20., 0.), (5., 0.), (-10., 0.), (10., 0.)] {
+            assert_eq!(
+                arc_path(x2, y2, 3.).commands(),
+                &[
+                    PathCommand::MoveTo([0., 0.]),
+                    PathCommand::LineTo([10., 0.])
+                ],
+                "({}, {})",
+                x2,
+                y2
+            );
+        }
+        //几乎共线
+        assert_eq!(arc_path(20., 1e-12, 3.).commands().len(), 2);
+    }
+
+    #[test]
+    fn arc_to_zero_radius_is_line() {
+        assert_eq!(
+            arc_path(10., 10., 0.).commands(),
+            &[
+                PathCommand::MoveTo([0., 0.]),
+                PathCommand::LineTo([10., 0.])
+            ]
+        );
+    }
+
+    #[test]
+    fn arc_to_without_current_point_moves() {
+        let mut path = Path::new();
+        path.arc_to(10., 0., 10., 10., 3.);
+        assert_eq!(path.commands(), &[PathCommand::MoveTo([10., 0.])]);
+    }
+
+    #[test]
+    fn arc_to_touches_both_lines() {
+        let path = arc_path(10., 10., 4.);
+        let near = |p: [f64; 2], x: f64, y: f64| (p[0] - x).abs() < 1e-9 && (p[1] - y).abs() < 1e-9;
+        match path.commands()[1] {
+            PathCommand::LineTo(p) => assert!(near(p, 6., 0.), "{:?}", p),
+            ref command => panic!("{:?}", command),
+        }
+        let (points, closed) = &path.flatten(1.)[0];
+        assert!(!closed);
+        assert!(near(points[points.len() - 1], 10., 4.));
+        //圆心为(6, 4)，展开的点都在圆上
+        for p in &points[1..] {
+            let r = (p[0] - 6.).hypot(p[1] - 4.);
+            assert!((r - 4.).abs() < 0.1, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn flatten_splits_subpaths() {
+        let mut path = Path::new();
+        path.move_to(0., 0.)
+            .line_to(10., 0.)
+            .line_to(10., 10.)
+            .close()
+            .line_to(0., 10.)
+            .move_to(50., 50.)
+            .move_to(20., 20.)
+            .line_to(30., 20.);
+        assert_eq!(
+            path.flatten(1.),
+            vec![
+                (vec![[0., 0.], [10., 0.], [10., 10.]], true),
+                //close之后从起点继续
+                (vec![[0., 0.], [0., 10.]], false),
+                //只有move_to的子路径被丢弃
+                (vec![[20., 20.], [30., 20.]], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn flatten_segments_follow_scale() {
+        let mut path = Path::new();
+        path.move_to(0., 0.).quad_to(50., 100., 100., 0.);
+        let small = path.flatten(1.)[0].0.len();
+        let large = path.flatten(16.)[0].0.len();
+        assert!(large > small, "{} {}", small, large);
+        //直线形状的曲线只分一段
+        let mut line = Path::new();
+        line.move_to(0., 0.).quad_to(50., 0., 100., 0.);
+        assert_eq!(line.flatten(1.)[0].0, vec![[0., 0.], [100., 0.]]);
+    }
+}
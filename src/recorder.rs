@@ -0,0 +1,163 @@
+//! 录制游戏画面，保存为GIF动画或PNG序列
+use super::{current_timestamp, Graphics, ImageData};
+use gif::SetParameter;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result};
+use std::path::Path;
+
+/// 画面录制器，由游戏持有，在draw的最后调用capture
+///
+/// 依赖 `Graphics::capture_frame`，在不支持读取画面的后端上不会录到任何帧。
+/// 按照真实时间采集，暂停或者慢动作时仍然正常录制
+///
+/// # Example
+///
+/// ```ignore
+/// fn event(&mut self, event: Event, _window: &mut impl Window) {
+///     if let Event::KeyUp(key) = event {
+///         if key == "F9" {
+///             if self.recorder.is_recording() {
+///                 self.recorder.stop();
+///                 self.recorder.save_gif("record.gif").unwrap();
+///             } else {
+///                 self.recorder.start();
+///             }
+///         }
+///     }
+/// }
+///
+/// fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {
+///     //...
+///     self.recorder.capture(g);
+/// }
+/// ```
+pub struct Recorder {
+    fps: f64,
+    //下一次采集的时间(毫秒)
+    next_time: f64,
+    recording: bool,
+    frames: Vec<ImageData>,
+    //每一帧采集时的时间(毫秒)
+    timestamps: Vec<f64>,
+}
+
+impl Recorder {
+    /// fps: 每秒采集的帧数，必须大于0
+    pub fn new(fps: f64) -> Recorder {
+        assert!(fps > 0.0 && fps.is_finite(), "录制帧率{}必须大于0", fps);
+        Recorder {
+            fps,
+            next_time: 0.0,
+            recording: false,
+            frames: vec![],
+            timestamps: vec![],
+        }
+    }
+
+    /// 清空之前的帧并开始录制
+    pub fn start(&mut self) {
+        self.frames.clear();
+        self.timestamps.clear();
+        self.next_time = current_timestamp();
+        self.recording = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.recording = false;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// 录制中并且到了采集时间时，读取当前画面
+    pub fn capture(&mut self, g: &mut impl Graphics) {
+        let now = current_timestamp();
+        if !self.recording || now < self.next_time {
+            return;
+        }
+        let interval = 1000.0 / self.fps;
+        self.next_time += interval;
+        //画面卡顿时不补录落下的帧
+        if self.next_time <= now {
+            self.next_time = now + interval;
+        }
+        if let Some(frame) = g.capture_frame() {
+            self.frames.push(frame);
+            self.timestamps.push(now);
+        }
+    }
+
+    pub fn frames(&self) -> &[ImageData] {
+        &self.frames
+    }
+
+    /// 每一帧采集的时间(毫秒)，和frames一一对应
+    pub fn timestamps(&self) -> &[f64] {
+        &self.timestamps
+    }
+
+    /// 保存为循环播放的GIF动画，每一帧的延时按照实际采集的时间计算
+    ///
+    /// GIF的宽高不能超过65535；所有帧的大小必须相同，录制中改变了窗口大小时返回错误
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let first = self
+            .frames
+            .first()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "没有录制到画面"))?;
+        let (width, height) = gif_size(first)?;
+        //先检查所有帧，不写出不完整的文件
+        if let Some(frame) = self
+            .frames
+            .iter()
+            .find(|frame| frame.width != first.width || frame.height != first.height)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "画面大小从{}x{}变为{}x{}，GIF的每一帧必须大小相同",
+                    first.width, first.height, frame.width, frame.height
+                ),
+            ));
+        }
+        let mut encoder =
+            gif::Encoder::new(BufWriter::new(File::create(path)?), width, height, &[])?;
+        encoder.set(gif::Repeat::Infinite)?;
+        //GIF的延时单位为1/100秒，按照累计时间取整，避免误差累积
+        let start = self.timestamps[0];
+        let centis = |time: f64| ((time - start) / 10.0).round() as i64;
+        for (i, image) in self.frames.iter().enumerate() {
+            let delay = match self.timestamps.get(i + 1) {
+                Some(next) => centis(*next) - centis(self.timestamps[i]),
+                //最后一帧没有下一帧的时间，使用采集间隔
+                None => (100.0 / self.fps).round() as i64,
+            };
+            let mut data = image.data.clone();
+            let mut frame = gif::Frame::from_rgba(width, height, &mut data);
+            frame.delay = delay.max(1).min(u16::MAX as i64) as u16;
+            encoder.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    /// 在目录中保存为 frame_00000.png, frame_00001.png ...
+    pub fn save_png_sequence<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            frame.save_png(dir.join(format!("frame_{:05}.png", i)))?;
+        }
+        Ok(())
+    }
+}
+
+/// GIF的宽高，超出u16范围时返回错误
+fn gif_size(image: &ImageData) -> Result<(u16, u16)> {
+    if image.width > u16::MAX as u32 || image.height > u16::MAX as u32 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("画面{}x{}超出GIF支持的大小", image.width, image.height),
+        ));
+    }
+    Ok((image.width as u16, image.height as u16))
+}
@@ -0,0 +1,285 @@
+use super::{Brush, DrawOptions, FillRule, Graphics, Image, Matrix2D, Path, Sampling, StrokeStyle};
+
+/// 一次绘图调用
+#[derive(Debug, Clone)]
+pub enum DrawCommand {
+    FillRect {
+        color: [u8; 4],
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    DrawImage {
+        transform: Option<Matrix2D>,
+        image: Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: DrawOptions,
+    },
+    DrawText {
+        content: String,
+        x: f64,
+        y: f64,
+        color: [u8; 4],
+        font_size: u32,
+    },
+    FillPolygon {
+        color: [u8; 4],
+        points: Vec<[f64; 2]>,
+    },
+    StrokePolyline {
+        color: [u8; 4],
+        points: Vec<[f64; 2]>,
+        closed: bool,
+        style: StrokeStyle,
+    },
+    FillPath {
+        color: [u8; 4],
+        path: Path,
+        rule: FillRule,
+    },
+    StrokePath {
+        color: [u8; 4],
+        path: Path,
+        style: StrokeStyle,
+    },
+    FillPolygonWith {
+        brush: Brush,
+        points: Vec<[f64; 2]>,
+    },
+    FillPathWith {
+        brush: Brush,
+        path: Path,
+        rule: FillRule,
+    },
+    Save,
+    Restore,
+    SetTransform(Matrix2D),
+    PushClip([f64; 4]),
+    PopClip,
+    SetSampling(Sampling),
+}
+
+impl DrawCommand {
+    /// 在指定的Graphics上执行这条命令
+    pub fn draw(&self, g: &mut impl Graphics) {
+        match self {
+            DrawCommand::FillRect {
+                color,
+                x,
+                y,
+                width,
+                height,
+            } => g.fill_rect(color, *x, *y, *width, *height),
+            DrawCommand::DrawImage {
+                transform,
+                image,
+                src,
+                dest,
+                options,
+            } => g.draw_image_with(*transform, image, *src, *dest, options),
+            DrawCommand::DrawText {
+                content,
+                x,
+                y,
+                color,
+                font_size,
+            } => g.draw_text(content, *x, *y, color, *font_size),
+            DrawCommand::FillPolygon { color, points } => g.fill_polygon(color, points),
+            DrawCommand::StrokePolyline {
+                color,
+                points,
+                closed,
+                style,
+            } => g.stroke_polyline(color, points, *closed, style),
+            DrawCommand::FillPath { color, path, rule } => g.fill_path(color, path, *rule),
+            DrawCommand::StrokePath { color, path, style } => g.stroke_path(color, path, style),
+            DrawCommand::FillPolygonWith { brush, points } => g.fill_polygon_with(brush, points),
+            DrawCommand::FillPathWith { brush, path, rule } => g.fill_path_with(brush, path, *rule),
+            DrawCommand::Save => g.save(),
+            DrawCommand::Restore => g.restore(),
+            DrawCommand::SetTransform(transform) => g.set_transform(*transform),
+            DrawCommand::PushClip(rect) => g.push_clip(*rect),
+            DrawCommand::PopClip => g.pop_clip(),
+            DrawCommand::SetSampling(sampling) => g.set_sampling(*sampling),
+        }
+    }
+}
+
+/// 不进行绘制，只记录绘图命令的Graphics
+///
+/// # Example
+///
+/// ```ignore
+/// let mut g = RecordingGraphics::new();
+/// sprite.draw(&mut g);
+/// assert_eq!(g.commands().len(), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecordingGraphics {
+    commands: Vec<DrawCommand>,
+    transform: Matrix2D,
+    stack: Vec<Matrix2D>,
+    sampling: Sampling,
+}
+
+impl RecordingGraphics {
+    pub fn new() -> RecordingGraphics {
+        RecordingGraphics::default()
+    }
+
+    /// 已记录的命令
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    /// 取出已记录的命令并清空
+    pub fn take_commands(&mut self) -> Vec<DrawCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    /// 清空命令，变换恢复为单位矩阵
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.transform = Matrix2D::IDENTITY;
+        self.stack.clear();
+    }
+
+    /// 将记录的命令按顺序重放到另一个Graphics
+    pub fn replay(&self, g: &mut impl Graphics) {
+        for command in &self.commands {
+            command.draw(g);
+        }
+    }
+}
+
+impl Graphics for RecordingGraphics {
+    fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
+        self.commands.push(DrawCommand::FillRect {
+            color: *color,
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    fn draw_image_with(
+        &mut self,
+        transform: Option<Matrix2D>,
+        image: &Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: &DrawOptions,
+    ) {
+        self.commands.push(DrawCommand::DrawImage {
+            transform,
+            image: image.clone(),
+            src,
+            dest,
+            options: *options,
+        });
+    }
+
+    fn draw_text(&mut self, cotnent: &str, x: f64, y: f64, color: &[u8; 4], font_size: u32) {
+        self.commands.push(DrawCommand::DrawText {
+            content: cotnent.to_string(),
+            x,
+            y,
+            color: *color,
+            font_size,
+        });
+    }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.commands.push(DrawCommand::FillPolygon {
+            color: *color,
+            points: points.to_vec(),
+        });
+    }
+
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        self.commands.push(DrawCommand::StrokePolyline {
+            color: *color,
+            points: points.to_vec(),
+            closed,
+            style: style.clone(),
+        });
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        self.commands.push(DrawCommand::FillPath {
+            color: *color,
+            path: path.clone(),
+            rule,
+        });
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        self.commands.push(DrawCommand::StrokePath {
+            color: *color,
+            path: path.clone(),
+            style: style.clone(),
+        });
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.commands.push(DrawCommand::FillPolygonWith {
+            brush: brush.clone(),
+            points: points.to_vec(),
+        });
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        self.commands.push(DrawCommand::FillPathWith {
+            brush: brush.clone(),
+            path: path.clone(),
+            rule,
+        });
+    }
+
+    fn save(&mut self) {
+        self.stack.push(self.transform);
+        self.commands.push(DrawCommand::Save);
+    }
+
+    fn restore(&mut self) {
+        if let Some(transform) = self.stack.pop() {
+            self.transform = transform;
+        }
+        self.commands.push(DrawCommand::Restore);
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.transform = transform;
+        self.commands.push(DrawCommand::SetTransform(transform));
+    }
+
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        self.commands.push(DrawCommand::PushClip(rect));
+    }
+
+    fn pop_clip(&mut self) {
+        self.commands.push(DrawCommand::PopClip);
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+        self.commands.push(DrawCommand::SetSampling(sampling));
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+}
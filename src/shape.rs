@@ -0,0 +1,357 @@
+use super::Matrix2D;
+use std::f64::consts::PI;
+
+/// 线段端点的样式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LineCap {
+    /// 在端点处截断
+    #[default]
+    Butt,
+    /// 半圆形端点
+    Round,
+    /// 端点向外延伸线宽的一半
+    Square,
+}
+
+/// 描边样式，线段的连接处统一使用圆角
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    /// 线宽
+    pub width: f64,
+    pub cap: LineCap,
+    /// 虚线的线段和间隔长度，为空时绘制实线
+    pub dash: Vec<f64>,
+    /// 虚线的起始偏移
+    pub dash_offset: f64,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            cap: LineCap::Butt,
+            dash: vec![],
+            dash_offset: 0.0,
+        }
+    }
+}
+
+impl StrokeStyle {
+    pub fn new(width: f64) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> StrokeStyle {
+        self.cap = cap;
+        self
+    }
+
+    /// 虚线，和canvas的setLineDash相同，dash长度为奇数时重复一次
+    pub fn with_dash(mut self, dash: &[f64], offset: f64) -> StrokeStyle {
+        self.dash = dash.to_vec();
+        self.dash_offset = offset;
+        self
+    }
+}
+
+/// 变换的平均缩放比例
+pub(crate) fn transform_scale(transform: &Matrix2D) -> f64 {
+    transform.determinant().abs().sqrt()
+}
+
+/// 将椭圆弧展开为折线，scale为当前变换的缩放，用于决定分段数量
+///
+/// 从start到end(弧度)，end小于start时逆时针绘制
+pub(crate) fn arc_points(
+    center: [f64; 2],
+    radius: [f64; 2],
+    start: f64,
+    end: f64,
+    scale: f64,
+) -> Vec<[f64; 2]> {
+    let sweep = (end - start).clamp(-2.0 * PI, 2.0 * PI);
+    let r = radius[0].abs().max(radius[1].abs()) * scale;
+    //弦高误差不超过0.1像素
+    let step = if r > 0.1 {
+        2.0 * (1.0 - 0.1 / r).acos()
+    } else {
+        PI / 2.0
+    };
+    let segments = ((sweep.abs() / step).ceil() as usize).clamp(4, 1024);
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f64 / segments as f64;
+            [
+                center[0] + radius[0] * angle.cos(),
+                center[1] + radius[1] * angle.sin(),
+            ]
+        })
+        .collect()
+}
+
+/// 圆角矩形的轮廓
+pub(crate) fn rounded_rect_points(rect: [f64; 4], radius: f64, scale: f64) -> Vec<[f64; 2]> {
+    let [x, y, w, h] = rect;
+    let r = radius.min(w.abs() / 2.0).min(h.abs() / 2.0).max(0.0);
+    let corners = [
+        ([x + w - r, y + r], -PI / 2.0),
+        ([x + w - r, y + h - r], 0.0),
+        ([x + r, y + h - r], PI / 2.0),
+        ([x + r, y + r], PI),
+    ];
+    let mut points = vec![];
+    for (center, start) in &corners {
+        points.extend(arc_points(*center, [r, r], *start, start + PI / 2.0, scale));
+    }
+    points
+}
+
+/// 多边形有向面积，顺时针(y轴向下)为正
+fn signed_area(points: &[[f64; 2]]) -> f64 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let [x0, y0] = points[i];
+        let [x1, y1] = points[(i + 1) % points.len()];
+        area += x0 * y1 - x1 * y0;
+    }
+    area / 2.0
+}
+
+/// 统一多边形的方向，使非零规则填充时重叠部分只绘制一次
+fn oriented(mut points: Vec<[f64; 2]>) -> Vec<[f64; 2]> {
+    if signed_area(&points) < 0.0 {
+        points.reverse();
+    }
+    points
+}
+
+/// 将折线描边转换为多个多边形，用非零规则一起填充
+pub(crate) fn stroke_polygons(
+    points: &[[f64; 2]],
+    closed: bool,
+    style: &StrokeStyle,
+    scale: f64,
+) -> Vec<Vec<[f64; 2]>> {
+    let mut polygons = vec![];
+    match dash_polylines(points, closed, style) {
+        Some(dashes) => {
+            for dash in dashes {
+                stroke_solid(&dash, false, style, scale, &mut polygons);
+            }
+        }
+        None => stroke_solid(points, closed, style, scale, &mut polygons),
+    }
+    polygons
+}
+
+/// 按照虚线样式将折线切分为多段，实线返回None
+fn dash_polylines(
+    points: &[[f64; 2]],
+    closed: bool,
+    style: &StrokeStyle,
+) -> Option<Vec<Vec<[f64; 2]>>> {
+    let total: f64 = style.dash.iter().sum();
+    if total <= 0.0 || !total.is_finite() || style.dash.iter().any(|d| *d < 0.0) {
+        return None;
+    }
+    let mut pattern = style.dash.clone();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_slice(&style.dash);
+    }
+    let total = total * (pattern.len() / style.dash.len()) as f64;
+    //跳过offset对应的长度
+    let (mut index, mut remaining) = (0, pattern[0]);
+    let mut skip = style.dash_offset.rem_euclid(total);
+    //offset为0时不跳过开头长度为0的线段
+    while skip > 0.0 && skip >= remaining {
+        skip -= remaining;
+        index = (index + 1) % pattern.len();
+        remaining = pattern[index];
+    }
+    remaining -= skip;
+
+    let mut points = points.to_vec();
+    if closed && !points.is_empty() {
+        points.push(points[0]);
+    }
+    let mut dashes = vec![];
+    let mut current: Vec<[f64; 2]> = vec![];
+    if index % 2 == 0 && !points.is_empty() {
+        current.push(points[0]);
+    }
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let len = (b[0] - a[0]).hypot(b[1] - a[1]);
+        let mut pos = 0.0;
+        while len - pos > remaining {
+            pos += remaining;
+            let t = pos / len;
+            let p = [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t];
+            current.push(p);
+            if index % 2 == 0 {
+                dashes.push(std::mem::take(&mut current));
+            }
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+        remaining -= len - pos;
+        if index % 2 == 0 {
+            current.push(b);
+        }
+    }
+    if index % 2 == 0 && current.len() > 1 {
+        dashes.push(current);
+    }
+    Some(dashes)
+}
+
+fn stroke_solid(
+    points: &[[f64; 2]],
+    closed: bool,
+    style: &StrokeStyle,
+    scale: f64,
+    polygons: &mut Vec<Vec<[f64; 2]>>,
+) {
+    let half = style.width / 2.0;
+    if points.is_empty() || half <= 0.0 {
+        return;
+    }
+    let circle = |p: [f64; 2]| oriented(arc_points(p, [half, half], 0.0, 2.0 * PI, scale));
+    let count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    for i in 0..count {
+        let mut a = points[i];
+        let mut b = points[(i + 1) % points.len()];
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            continue;
+        }
+        let (ux, uy) = (dx / len, dy / len);
+        if !closed && style.cap == LineCap::Square {
+            if i == 0 {
+                a = [a[0] - ux * half, a[1] - uy * half];
+            }
+            if i == count - 1 {
+                b = [b[0] + ux * half, b[1] + uy * half];
+            }
+        }
+        let (nx, ny) = (-uy * half, ux * half);
+        polygons.push(oriented(vec![
+            [a[0] + nx, a[1] + ny],
+            [b[0] + nx, b[1] + ny],
+            [b[0] - nx, b[1] - ny],
+            [a[0] - nx, a[1] - ny],
+        ]));
+    }
+    //圆角连接
+    let joins = if closed {
+        0..points.len()
+    } else {
+        1..points.len() - 1
+    };
+    for i in joins {
+        polygons.push(circle(points[i]));
+    }
+    if !closed && style.cap == LineCap::Round {
+        polygons.push(circle(points[0]));
+        polygons.push(circle(points[points.len() - 1]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //x轴上从0到len的直线
+    fn dashes(dash: &[f64], offset: f64, len: f64) -> Option<Vec<Vec<[f64; 2]>>> {
+        let style = StrokeStyle::new(1.).with_dash(dash, offset);
+        dash_polylines(&[[0., 0.], [len, 0.]], false, &style).map(|dashes| {
+            dashes
+                .into_iter()
+                .map(|dash| {
+                    dash.into_iter()
+                        .map(|p| [(p[0] * 1e6).round() / 1e6, (p[1] * 1e6).round() / 1e6])
+                        .collect()
+                })
+                .collect()
+        })
+    }
+
+    fn segments(ranges: &[(f64, f64)]) -> Option<Vec<Vec<[f64; 2]>>> {
+        Some(
+            ranges
+                .iter()
+                .map(|&(a, b)| vec![[a, 0.], [b, 0.]])
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn even_pattern() {
+        assert_eq!(
+            dashes(&[4., 2.], 0., 14.),
+            segments(&[(0., 4.), (6., 10.), (12., 14.)])
+        );
+    }
+
+    #[test]
+    fn odd_pattern_repeats() {
+        //[3]相当于[3, 3]
+        assert_eq!(dashes(&[3.], 0., 12.), segments(&[(0., 3.), (6., 9.)]));
+        //[2, 1, 3]相当于[2, 1, 3, 2, 1, 3]
+        assert_eq!(
+            dashes(&[2., 1., 3.], 0., 14.),
+            segments(&[(0., 2.), (3., 6.), (8., 9.), (12., 14.)])
+        );
+    }
+
+    #[test]
+    fn zero_length_dashes_are_dots() {
+        assert_eq!(
+            dashes(&[0., 5.], 0., 12.),
+            segments(&[(0., 0.), (5., 5.), (10., 10.)])
+        );
+        //长度为0的间隔不断开
+        assert_eq!(
+            dashes(&[5., 0.], 0., 12.),
+            segments(&[(0., 5.), (5., 10.), (10., 12.)])
+        );
+    }
+
+    #[test]
+    fn invalid_pattern_is_solid() {
+        assert_eq!(dashes(&[], 0., 10.), None);
+        assert_eq!(dashes(&[0., 0.], 0., 10.), None);
+        assert_eq!(dashes(&[4., -2.], 0., 10.), None);
+        assert_eq!(dashes(&[4., f64::INFINITY], 0., 10.), None);
+    }
+
+    #[test]
+    fn offset_wraps() {
+        let expected = segments(&[(0., 3.), (5., 9.), (11., 12.)]);
+        assert_eq!(dashes(&[4., 2.], 1., 12.), expected);
+        assert_eq!(dashes(&[4., 2.], 7., 12.), expected);
+        assert_eq!(dashes(&[4., 2.], 601., 12.), expected);
+        //负数offset向后移动
+        assert_eq!(dashes(&[4., 2.], -5., 12.), expected);
+        //offset刚好在间隔开始的位置
+        assert_eq!(dashes(&[4., 2.], 4., 12.), segments(&[(2., 6.), (8., 12.)]));
+    }
+
+    #[test]
+    fn closed_polyline_dashes_last_edge() {
+        let style = StrokeStyle::new(1.).with_dash(&[3., 1.], 0.);
+        let square = [[0., 0.], [4., 0.], [4., 4.], [0., 4.]];
+        let dashes = dash_polylines(&square, true, &style).unwrap();
+        assert_eq!(dashes.len(), 4);
+        //最后一段虚线在闭合的边上
+        assert_eq!(dashes[3], vec![[0., 4.], [0., 1.]]);
+    }
+}
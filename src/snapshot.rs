@@ -0,0 +1,143 @@
+//! 截图对比测试：将渲染结果与保存在仓库中的PNG参考图逐像素比较
+//!
+//! 设置环境变量 `MENGINE_UPDATE_SNAPSHOTS=1` 时，会用当前结果覆盖(或创建)参考图。
+use super::{run_headless, Settings, State};
+use image::{Rgba, RgbaImage};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// 使用run_headless运行State，每帧更新一次，返回第N帧的画面
+pub fn render_frames<S: State>(
+    width: f64,
+    height: f64,
+    settings: Settings,
+    frames: u32,
+) -> RgbaImage {
+    let mut runner = run_headless::<S>(width, height, settings);
+    for _ in 0..frames.max(1) {
+        runner.step(1);
+    }
+    runner.frame().clone()
+}
+
+/// 在参考图文件名后追加后缀，如 `title.png` => `title.diff.png`
+fn sibling_path(reference: &Path, suffix: &str) -> PathBuf {
+    let stem = reference
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    reference.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+/// 保存图片，目录不存在时先创建
+fn save(image: &RgbaImage, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    image.save(path)
+}
+
+/// 将画面与参考图比较，每个像素的各通道差值不能超过tolerance
+///
+/// 比较失败时，在参考图旁边写入 `*.actual.png` 和 `*.diff.png`(不同的像素标记为红色)
+pub fn compare_snapshot<P: AsRef<Path>>(
+    frame: &RgbaImage,
+    reference: P,
+    tolerance: u8,
+) -> Result<()> {
+    let reference = reference.as_ref();
+    let actual_path = sibling_path(reference, "actual");
+    let diff_path = sibling_path(reference, "diff");
+
+    if std::env::var("MENGINE_UPDATE_SNAPSHOTS")
+        .map(|v| v == "1")
+        .unwrap_or(false)
+    {
+        return save(frame, reference);
+    }
+
+    let expected = match image::open(reference) {
+        Ok(image) => image.to_rgba(),
+        Err(_) => {
+            save(frame, &actual_path)?;
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "参考图{:?}不存在，当前结果已保存到{:?}",
+                    reference, actual_path
+                ),
+            ));
+        }
+    };
+
+    if expected.dimensions() != frame.dimensions() {
+        save(frame, &actual_path)?;
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "尺寸不一致: 参考图{:?}, 当前结果{:?}",
+                expected.dimensions(),
+                frame.dimensions()
+            ),
+        ));
+    }
+
+    let mut diff = RgbaImage::new(frame.width(), frame.height());
+    let mut mismatched = 0;
+    for (x, y, actual) in frame.enumerate_pixels() {
+        let expected = expected.get_pixel(x, y);
+        let max_delta = actual
+            .0
+            .iter()
+            .zip(expected.0.iter())
+            .map(|(a, b)| (*a as i16 - *b as i16).unsigned_abs() as u8)
+            .max()
+            .unwrap_or(0);
+        if max_delta > tolerance {
+            mismatched += 1;
+            diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            //相同的像素变淡显示
+            let gray = (actual[0] as u32 + actual[1] as u32 + actual[2] as u32) / 3;
+            let faded = (gray / 4 + 191) as u8;
+            diff.put_pixel(x, y, Rgba([faded, faded, faded, 255]));
+        }
+    }
+
+    if mismatched > 0 {
+        save(frame, &actual_path)?;
+        save(&diff, &diff_path)?;
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{}个像素与参考图{:?}不同(容差{})，差异图已保存到{:?}",
+                mismatched, reference, tolerance, diff_path
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// 渲染N帧并与参考图比较，不一致时panic
+///
+/// # Example
+///
+/// ```ignore
+/// #[test]
+/// fn title_screen() {
+///     mengine::assert_snapshot::<Game>(300., 300., Settings::default(), 10, "tests/snapshots/title.png", 2);
+/// }
+/// ```
+pub fn assert_snapshot<S: State, P: AsRef<Path>>(
+    width: f64,
+    height: f64,
+    settings: Settings,
+    frames: u32,
+    reference: P,
+    tolerance: u8,
+) {
+    let frame = render_frames::<S>(width, height, settings, frames);
+    if let Err(err) = compare_snapshot(&frame, reference, tolerance) {
+        panic!("snapshot mismatch: {}", err);
+    }
+}
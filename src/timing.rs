@@ -0,0 +1,233 @@
+use std::cell::RefCell;
+
+/// 传给update和draw的时间信息
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameTime {
+    /// 每次update的固定时间间隔(秒)
+    pub dt: f64,
+    /// 游戏开始之后所有update累计的时间(秒)
+    pub total: f64,
+    /// 距离上一次update经过的时间占dt的比例(0~1)，绘制时用于在两次update之间插值
+    pub alpha: f64,
+}
+
+/// 固定时间步长，按照ups计算每一帧需要调用几次update
+///
+/// # Example
+///
+/// ```ignore
+/// for _ in 0..timestep.advance(current_timestamp()) {
+///     let time = timestep.step();
+///     game.update(&mut window, time);
+/// }
+/// game.draw(&mut graphics, &mut window, timestep.frame_time());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    dt: f64,
+    max_updates: u32,
+    accumulator: f64,
+    total: f64,
+    last_timestamp: Option<f64>,
+}
+
+impl FixedTimestep {
+    /// ups: 每秒update次数(为0时按1处理)，max_updates: 一次最多补偿的update次数
+    pub fn new(ups: u64, max_updates: u32) -> FixedTimestep {
+        FixedTimestep {
+            dt: dt(ups),
+            max_updates: max_updates.max(1),
+            accumulator: 0.0,
+            total: 0.0,
+            last_timestamp: None,
+        }
+    }
+
+    /// 修改每秒update次数，为0时按1处理
+    pub fn set_ups(&mut self, ups: u64) {
+        self.dt = dt(ups);
+    }
+
+    /// 每次update的时间间隔(秒)
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    /// 时间前进到now(毫秒)，返回需要调用update的次数
+    ///
+    /// 经过的时间先交给游戏时钟，按照time_scale缩放，暂停时不再调用update。
+    /// 窗口被拖动或者页面在后台时会积累大量时间，超过max_updates的部分直接丢弃
+    pub fn advance(&mut self, now: f64) -> u32 {
+        let elapsed = match self.last_timestamp {
+            Some(last) => (now - last).max(0.0),
+            None => 0.0,
+        };
+        self.last_timestamp = Some(now);
+        self.accumulator += GAME_CLOCK.with(|clock| clock.borrow_mut().advance(elapsed)) / 1000.0;
+        //浮点误差可能让刚好一个dt的时间算成0次
+        let updates = (self.accumulator / self.dt + 1e-9).floor() as u32;
+        if updates > self.max_updates {
+            let dropped = (updates - self.max_updates) as f64 * self.dt;
+            self.accumulator -= dropped;
+            //丢弃的时间也不计入游戏时钟
+            GAME_CLOCK.with(|clock| clock.borrow_mut().time -= dropped * 1000.0);
+            self.max_updates
+        } else {
+            updates
+        }
+    }
+
+    /// 消耗一个dt，返回本次update的时间信息
+    pub fn step(&mut self) -> FrameTime {
+        self.accumulator = (self.accumulator - self.dt).max(0.0);
+        self.total += self.dt;
+        FrameTime {
+            dt: self.dt,
+            total: self.total,
+            alpha: 0.0,
+        }
+    }
+
+    /// 绘制使用的时间信息
+    pub fn frame_time(&self) -> FrameTime {
+        FrameTime {
+            dt: self.dt,
+            total: self.total,
+            alpha: (self.accumulator / self.dt).min(1.0),
+        }
+    }
+}
+
+//ups为0时dt会变成无穷大
+fn dt(ups: u64) -> f64 {
+    1.0 / ups.max(1) as f64
+}
+
+/// 游戏时钟，按照time_scale缩放，暂停时停止
+#[derive(Debug, Clone)]
+struct GameClock {
+    time: f64,
+    scale: f64,
+    paused: bool,
+}
+
+impl GameClock {
+    const fn new() -> GameClock {
+        GameClock {
+            time: 0.0,
+            scale: 1.0,
+            paused: false,
+        }
+    }
+
+    /// 经过了elapsed毫秒的真实时间，返回经过的游戏时间(毫秒)
+    fn advance(&mut self, elapsed: f64) -> f64 {
+        let elapsed = if self.paused {
+            0.0
+        } else {
+            elapsed * self.scale
+        };
+        self.time += elapsed;
+        elapsed
+    }
+}
+
+thread_local! {
+    static GAME_CLOCK: RefCell<GameClock> = const { RefCell::new(GameClock::new()) };
+}
+
+/// 游戏时间(毫秒)，受Window::set_time_scale、pause和resume影响
+///
+/// AnimationTimer使用游戏时间，暂停时动画也会停止
+pub fn game_timestamp() -> f64 {
+    GAME_CLOCK.with(|clock| clock.borrow().time)
+}
+
+pub(crate) fn set_time_scale(scale: f64) {
+    GAME_CLOCK.with(|clock| clock.borrow_mut().scale = scale.max(0.0));
+}
+
+pub(crate) fn time_scale() -> f64 {
+    GAME_CLOCK.with(|clock| clock.borrow().scale)
+}
+
+pub(crate) fn set_paused(paused: bool) {
+    GAME_CLOCK.with(|clock| clock.borrow_mut().paused = paused);
+}
+
+pub(crate) fn is_paused() -> bool {
+    GAME_CLOCK.with(|clock| clock.borrow().paused)
+}
+
+/// 游戏时钟归零并恢复正常速度
+#[cfg(not(any(target_arch = "asmjs", target_arch = "wasm32")))]
+pub(crate) fn reset_game_clock() {
+    GAME_CLOCK.with(|clock| *clock.borrow_mut() = GameClock::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_ups_keeps_dt_finite() {
+        let mut timestep = FixedTimestep::new(0, 5);
+        assert_eq!(timestep.dt(), 1.0);
+        timestep.set_ups(0);
+        assert_eq!(timestep.dt(), 1.0);
+        timestep.advance(0.0);
+        assert_eq!(timestep.advance(2000.0), 2);
+    }
+
+    #[test]
+    fn first_advance_runs_nothing() {
+        let mut timestep = FixedTimestep::new(60, 5);
+        assert_eq!(timestep.advance(123456.0), 0);
+    }
+
+    #[test]
+    fn exact_dt_counts_as_one_update() {
+        let mut timestep = FixedTimestep::new(60, 5);
+        let mut now = 0.0;
+        timestep.advance(now);
+        let mut updates = 0;
+        for _ in 0..600 {
+            now += 1000.0 / 60.0;
+            let n = timestep.advance(now);
+            //没有误差补偿时偶尔会出现0次和2次交替
+            assert_eq!(n, 1, "now = {}", now);
+            for _ in 0..n {
+                timestep.step();
+            }
+            updates += n;
+        }
+        assert_eq!(updates, 600);
+        assert!((timestep.frame_time().total - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn drops_time_beyond_max_updates() {
+        let mut timestep = FixedTimestep::new(60, 5);
+        timestep.advance(0.0);
+        assert_eq!(timestep.advance(1000.0), 5);
+        for _ in 0..5 {
+            timestep.step();
+        }
+        //丢弃的时间不计入游戏时钟
+        assert!((game_timestamp() - 5000.0 / 60.0).abs() < 1e-9);
+        assert!(timestep.frame_time().alpha < 1.0);
+        //之后按照正常速度继续
+        assert_eq!(timestep.advance(1000.0 + 1000.0 / 60.0), 1);
+    }
+
+    #[test]
+    fn paused_clock_runs_no_updates() {
+        let mut timestep = FixedTimestep::new(60, 5);
+        timestep.advance(0.0);
+        set_paused(true);
+        assert_eq!(timestep.advance(500.0), 0);
+        assert_eq!(game_timestamp(), 0.0);
+        set_paused(false);
+        assert_eq!(timestep.advance(500.0 + 1000.0 / 60.0), 1);
+    }
+}
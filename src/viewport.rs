@@ -0,0 +1,112 @@
+/// 游戏画面缩放到窗口的方式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScaleMode {
+    /// 不缩放(只按照屏幕缩放比例放大)，画面在窗口中居中
+    #[default]
+    None,
+    /// 等比缩放到完整显示在窗口中，空白部分填充letterbox_color
+    Fit,
+    /// 等比缩放到填满窗口，超出窗口的部分被裁掉
+    Fill,
+    /// 拉伸到填满窗口，不保持宽高比
+    Stretch,
+    /// 按物理像素整数倍等比缩放，适合像素风格的游戏，窗口小于画面时不缩放
+    IntegerPixelPerfect,
+}
+
+/// 游戏画面在窗口中的位置和缩放
+///
+/// 窗口坐标(物理像素) = 游戏坐标 * scale + (x, y)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f64,
+    pub y: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    /// 游戏画面大小
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Viewport {
+    /// 按照mode计算大小为game_size的画面在窗口中的位置
+    ///
+    /// window_size为窗口的物理像素大小，scale_factor为屏幕缩放比例
+    pub fn new(
+        mode: ScaleMode,
+        game_size: (f64, f64),
+        window_size: (f64, f64),
+        scale_factor: f64,
+    ) -> Viewport {
+        let (width, height) = game_size;
+        let (window_width, window_height) = window_size;
+        let fit = (window_width / width).min(window_height / height);
+        let (scale_x, scale_y) = match mode {
+            ScaleMode::None => (scale_factor, scale_factor),
+            ScaleMode::Fit => (fit, fit),
+            ScaleMode::Fill => {
+                let fill = (window_width / width).max(window_height / height);
+                (fill, fill)
+            }
+            ScaleMode::Stretch => (window_width / width, window_height / height),
+            ScaleMode::IntegerPixelPerfect => {
+                let scale = fit.floor().max(1.0);
+                (scale, scale)
+            }
+        };
+        //窗口最小化时大小为0
+        let (scale_x, scale_y) = if scale_x.is_finite() && scale_x > 0.0 && scale_y > 0.0 {
+            (scale_x, scale_y)
+        } else {
+            (1.0, 1.0)
+        };
+        Viewport {
+            x: (window_width - width * scale_x) / 2.0,
+            y: (window_height - height * scale_y) / 2.0,
+            scale_x,
+            scale_y,
+            width,
+            height,
+        }
+    }
+
+    /// 窗口坐标转换为游戏坐标
+    pub fn to_game(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.x) / self.scale_x, (y - self.y) / self.scale_y)
+    }
+
+    /// 游戏坐标转换为窗口坐标
+    pub fn to_window(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.scale_x + self.x, y * self.scale_y + self.y)
+    }
+
+    /// 画面在窗口中的区域[x, y, width, height]
+    pub fn rect(&self) -> [f64; 4] {
+        [
+            self.x,
+            self.y,
+            self.width * self.scale_x,
+            self.height * self.scale_y,
+        ]
+    }
+
+    /// 窗口中画面以外需要填充letterbox_color的区域[x, y, width, height]
+    pub fn letterbox(&self, window_size: (f64, f64)) -> Vec<[f64; 4]> {
+        let (window_width, window_height) = window_size;
+        let [x, y, width, height] = self.rect();
+        let (right, bottom) = (x + width, y + height);
+        vec![
+            //上
+            [0.0, 0.0, window_width, y],
+            //下
+            [0.0, bottom, window_width, window_height - bottom],
+            //左
+            [0.0, y, x, height],
+            //右
+            [right, y, window_width - right, height],
+        ]
+        .into_iter()
+        .filter(|rect| rect[2] > 0.0 && rect[3] > 0.0)
+        .collect()
+    }
+}
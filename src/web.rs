@@ -1,6 +1,5 @@
 use askama::Template;
 use std::io::Result;
-use std::path::Path;
 use std::rc::Rc;
 use std::sync::mpsc::{channel, Sender};
 use stdweb::traits::*;
@@ -8,6 +7,7 @@ use stdweb::unstable::TryInto;
 use stdweb::web::event::ReadyStateChangeEvent;
 use stdweb::web::html_element::ImageElement;
 use stdweb::web::ArrayBuffer;
+use stdweb::web::TypedArray;
 use stdweb::web::IElement;
 use stdweb::web::IEventTarget;
 use stdweb::web::XhrReadyState;
@@ -16,13 +16,15 @@ use stdweb::web::XmlHttpRequest;
 use stdweb::web::{document, CanvasRenderingContext2d};
 
 use super::{
-    AnimationTimer, Assets, AssetsType, AudioType, Event, Graphics, Settings, State, Transform,
-    Window,
+    Assets, AssetsType, AudioType, Backend, BlendMode, Brush, Canvas, CursorIcon, DrawOptions,
+    Event, FillRule, FixedTimestep, GradientStop, Graphics, Image, ImageData, LineCap, Matrix2D,
+    NativeCanvas, NativeImage, Path, PathCommand, Sampling, Settings, Sound, State, StrokeStyle,
+    Viewport, Window,
 };
 use std::cell::RefCell;
 use stdweb::web::event::{
-    ClickEvent, ITouchEvent, KeyDownEvent, KeyUpEvent, MouseDownEvent, PointerMoveEvent,
-    ResizeEvent, TouchMove,
+    BlurEvent, ClickEvent, FocusEvent, ITouchEvent, KeyDownEvent, KeyUpEvent, MouseDownEvent,
+    PointerMoveEvent, ResizeEvent, TouchMove,
 };
 use stdweb::web::html_element::CanvasElement;
 
@@ -32,38 +34,101 @@ enum RawAssets {
     Value(stdweb::Value),
 }
 
-#[derive(Debug, Clone)]
-pub struct Sound {
-    audio_type: AudioType,
-    buffer: stdweb::Value,
-}
-
-#[derive(Debug, Clone)]
-pub struct Image {
-    image: ImageElement,
+/// 浏览器中的<img>元素
+impl NativeImage for ImageElement {
+    fn width(&self) -> f64 {
+        ImageElement::width(self) as f64
+    }
+    fn height(&self) -> f64 {
+        ImageElement::height(self) as f64
+    }
 }
 
-impl Image {
-    pub fn width(&self) -> f64 {
-        self.image.width() as f64
+/// 离屏画布转换的<canvas>元素
+impl NativeImage for CanvasElement {
+    fn width(&self) -> f64 {
+        CanvasElement::width(self) as f64
     }
-    pub fn height(&self) -> f64 {
-        self.image.height() as f64
+    fn height(&self) -> f64 {
+        CanvasElement::height(self) as f64
     }
 }
 
 pub struct BrowserWindow {
-    timer: AnimationTimer,
+    timestep: FixedTimestep,
     ups_count: u64,
     fps_count: u64,
     ups: u64,
     fps: u64,
-    ups_fps_timer: AnimationTimer,
+    //下一次统计UPS/FPS的时间
+    next_log_time: f64,
     sender: Sender<(String, AssetsType, Result<RawAssets>)>,
+    cursor_visible: bool,
+    cursor_icon: CursorIcon,
+    quit: bool,
+    scale_factor: f64,
+    sampling: Sampling,
+}
+impl BrowserWindow {
+    //canvas的style会在窗口大小改变时被覆盖，光标设置在body上
+    fn update_cursor(&self) {
+        let cursor = if self.cursor_visible {
+            css_cursor(self.cursor_icon)
+        } else {
+            "none"
+        };
+        js! {
+            document.body.style.cursor = @{cursor};
+        };
+    }
 }
 impl Window for BrowserWindow {
     fn set_update_rate(&mut self, ups: u64) {
-        self.timer.set_fps(ups as f64);
+        self.timestep.set_ups(ups);
+    }
+
+    fn set_title(&mut self, title: &str) {
+        document().set_title(title);
+    }
+
+    /// 浏览器只允许在用户操作(点击、按键)的事件中进入全屏
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        js! {
+            if (@{fullscreen}) {
+                var canvas = document.getElementById("canvas");
+                if (canvas.requestFullscreen) {
+                    canvas.requestFullscreen();
+                } else if (canvas.webkitRequestFullscreen) {
+                    canvas.webkitRequestFullscreen();
+                }
+            } else if (document.fullscreenElement) {
+                document.exitFullscreen();
+            } else if (document.webkitFullscreenElement) {
+                document.webkitExitFullscreen();
+            }
+        };
+    }
+
+    /// 浏览器窗口大小不能由页面修改，忽略
+    fn set_size(&mut self, _width: f64, _height: f64) {}
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+        self.update_cursor();
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.cursor_icon = icon;
+        self.update_cursor();
+    }
+
+    /// 停止update和draw，页面保持最后一帧
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
     }
 
     fn load_assets(&mut self, assets: &[(&str, AssetsType)]) {
@@ -154,25 +219,16 @@ impl Window for BrowserWindow {
         }
     }
 
-    fn load_image_bgra(&mut self, width: u32, height: u32, key: &str, data: Vec<u8>) {
-        let mut buffer = Vec::with_capacity(data.len());
-        for pixel in data.chunks(4){
-            buffer.push(pixel[2]);//r
-            buffer.push(pixel[1]);//g
-            buffer.push(pixel[0]);//b
-            buffer.push(pixel[3]);//a
-        }
-        self.load_image(width, height, key, buffer)
-    }
-
-    fn load_image(&mut self, width: u32, height: u32, key: &str, data: Vec<u8>) {
-        let mut png_data: Vec<u8> = vec![];
-        {
-            let mut encoder = png::Encoder::new(&mut png_data, width, height);
-            encoder.set_color(png::ColorType::RGBA);
-            let mut writer = encoder.write_header().unwrap();
-            writer.write_image_data(&data).unwrap();
-        }
+    fn load_image_data(&mut self, key: &str, image: ImageData) {
+        let png_data = match image.to_png() {
+            Ok(png_data) => png_data,
+            Err(err) => {
+                let _ = self
+                    .sender
+                    .send((String::from(key), AssetsType::Image, Err(err)));
+                return;
+            }
+        };
 
         let mut png_base64 = base64::encode(&png_data);
         png_base64.insert_str(0, "data:image/png;base64,");
@@ -196,78 +252,248 @@ impl Window for BrowserWindow {
             Ok(RawAssets::Image(image)),
         ));
     }
+
+    fn create_canvas(&self, width: f64, height: f64) -> Canvas {
+        let canvas = create_canvas_element(width.ceil() as u32, height.ceil() as u32);
+        let graphics = BrowserGraphics::new(canvas.get_context().unwrap(), self.sampling);
+        Canvas::new(width, height, graphics)
+    }
 }
 
 pub struct BrowserGraphics {
     context: CanvasRenderingContext2d,
+    //窗口的缩放和平移
+    base_transform: Matrix2D,
+    transform: Matrix2D,
+    stack: Vec<Matrix2D>,
+    //push_clip的次数，每次裁剪都保存一次context的状态
+    clips: usize,
+    sampling: Sampling,
 }
 
 impl BrowserGraphics {
-    fn transform(&self, transform: Option<Transform>) {
+    fn new(context: CanvasRenderingContext2d, sampling: Sampling) -> BrowserGraphics {
+        BrowserGraphics {
+            context,
+            base_transform: Matrix2D::IDENTITY,
+            transform: Matrix2D::IDENTITY,
+            stack: vec![],
+            clips: 0,
+            sampling,
+        }
+    }
+
+    /// 清空save保存的状态和裁剪区域，base为窗口的缩放和平移
+    fn reset_state(&mut self, base: Matrix2D) {
+        //游戏中没有restore的状态
+        for _ in 0..self.stack.len() + self.clips {
+            self.context.restore();
+        }
+        self.stack.clear();
+        self.clips = 0;
+        self.base_transform = base;
+        self.transform = Matrix2D::IDENTITY;
+        self.context
+            .set_transform(base.a, base.b, base.c, base.d, base.e, base.f);
+    }
+
+    /// 保存状态之后叠加一次绘图调用的变换，绘制完成后需要调用context.restore()
+    fn apply_transform(&self, transform: Option<Matrix2D>) {
         self.context.save();
-        if let Some(transform) = transform {
-            self.context
-                .translate(transform.translate.0, transform.translate.1);
-            self.context.rotate(transform.rotate);
+        if let Some(m) = transform {
+            self.context.transform(m.a, m.b, m.c, m.d, m.e, m.f);
+        }
+    }
+
+    /// 用points创建新的路径
+    fn build_path(&self, points: &[[f64; 2]], closed: bool) {
+        self.context.begin_path();
+        for (i, [x, y]) in points.iter().enumerate() {
+            if i == 0 {
+                self.context.move_to(*x, *y);
+            } else {
+                self.context.line_to(*x, *y);
+            }
+        }
+        if closed {
+            self.context.close_path();
+        }
+    }
+
+    /// 用Path的命令创建新的路径
+    fn build_commands(&self, path: &Path) {
+        self.context.begin_path();
+        for command in path.commands() {
+            match command {
+                PathCommand::MoveTo([x, y]) => self.context.move_to(*x, *y),
+                PathCommand::LineTo([x, y]) => self.context.line_to(*x, *y),
+                PathCommand::QuadTo([cx, cy], [x, y]) => {
+                    self.context.quadratic_curve_to(*cx, *cy, *x, *y)
+                }
+                PathCommand::CubicTo([c1x, c1y], [c2x, c2y], [x, y]) => {
+                    self.context.bezier_curve_to(*c1x, *c1y, *c2x, *c2y, *x, *y)
+                }
+                PathCommand::Close => self.context.close_path(),
+            }
+        }
+    }
+
+    /// 设置fillStyle，渐变和图案的坐标在填充时使用当前变换
+    fn set_fill_brush(&self, brush: &Brush) {
+        match brush {
+            Brush::Solid(color) => self.context.set_fill_style_color(&format!(
+                "rgba({},{},{},{})",
+                color[0],
+                color[1],
+                color[2],
+                color[3] as f64 / 255.0
+            )),
+            Brush::LinearGradient { start, end, stops } => {
+                let (offsets, colors) = gradient_stops(stops);
+                js! {
+                    @(no_return)
+                    var ctx = @{&self.context};
+                    var gradient = ctx.createLinearGradient(@{start[0]}, @{start[1]}, @{end[0]}, @{end[1]});
+                    var offsets = @{offsets}, colors = @{colors};
+                    for (var i = 0; i < offsets.length; i++) {
+                        gradient.addColorStop(offsets[i], colors[i]);
+                    }
+                    ctx.fillStyle = gradient;
+                }
+            }
+            Brush::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                let (offsets, colors) = gradient_stops(stops);
+                let [x, y] = *center;
+                //半径为负数时createRadialGradient会抛出异常
+                let radius = radius.max(0.0);
+                js! {
+                    @(no_return)
+                    var ctx = @{&self.context};
+                    var gradient = ctx.createRadialGradient(@{x}, @{y}, 0, @{x}, @{y}, @{radius});
+                    var offsets = @{offsets}, colors = @{colors};
+                    for (var i = 0; i < offsets.length; i++) {
+                        gradient.addColorStop(offsets[i], colors[i]);
+                    }
+                    ctx.fillStyle = gradient;
+                }
+            }
+            Brush::Pattern { image, transform } => {
+                let image = match image_source(image) {
+                    Some(image) => image,
+                    None => return,
+                };
+                let m = transform;
+                js! {
+                    @(no_return)
+                    var ctx = @{&self.context};
+                    var pattern = ctx.createPattern(@{image}, "repeat");
+                    if (pattern && pattern.setTransform) {
+                        pattern.setTransform(new DOMMatrix([@{m.a}, @{m.b}, @{m.c}, @{m.d}, @{m.e}, @{m.f}]));
+                    }
+                    ctx.fillStyle = pattern || "transparent";
+                }
+            }
+        }
+    }
+
+    fn set_stroke_style(&self, color: &[u8; 4], style: &StrokeStyle) {
+        self.context.set_stroke_style_color(&format!(
+            "rgba({},{},{},{})",
+            color[0],
+            color[1],
+            color[2],
+            color[3] as f64 / 255.0
+        ));
+        self.context.set_line_width(style.width);
+        self.context.set_line_cap(match style.cap {
+            LineCap::Butt => stdweb::web::LineCap::Butt,
+            LineCap::Round => stdweb::web::LineCap::Round,
+            LineCap::Square => stdweb::web::LineCap::Square,
+        });
+        self.context.set_line_join(stdweb::web::LineJoin::Round);
+        js! {
+            @(no_return)
+            var ctx = @{&self.context};
+            ctx.setLineDash(@{&style.dash});
+            ctx.lineDashOffset = @{style.dash_offset};
         }
     }
 }
 
 impl Graphics for BrowserGraphics {
     fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
-        self.context.set_fill_style_color(&format!(
-            "rgba({},{},{},{})",
-            color[0], color[1], color[2], color[3]
-        ));
+        self.set_fill_brush(&Brush::Solid(*color));
         self.context.fill_rect(x, y, width, height);
     }
 
-    fn draw_image(
+    fn draw_image_with(
         &mut self,
-        transform: Option<Transform>,
+        transform: Option<Matrix2D>,
         image: &Image,
         src: Option<[f64; 4]>,
         dest: Option<[f64; 4]>,
+        options: &DrawOptions,
     ) {
-        self.transform(transform);
-
-        let _ = match if src.is_none() && dest.is_none() {
-            self.context.draw_image(image.image.clone(), 0., 0.)
-        } else if src.is_none() && dest.is_some() {
-            let dest = dest.unwrap();
-            self.context
-                .draw_image_d(image.image.clone(), dest[0], dest[1], dest[2], dest[3])
-        } else if src.is_some() && dest.is_none() {
-            let src = src.unwrap();
-            self.context.draw_image_s(
-                image.image.clone(),
-                src[0],
-                src[1],
-                src[2],
-                src[3],
-                0.,
-                0.,
-                image.image.width().into(),
-                image.image.height().into(),
-            )
-        } else {
-            let src = src.unwrap();
-            let dest = dest.unwrap();
-            self.context.draw_image_s(
-                image.image.clone(),
-                src[0],
-                src[1],
-                src[2],
-                src[3],
-                dest[0],
-                dest[1],
-                dest[2],
-                dest[3],
-            )
-        } {
-            Err(err) => Err(format!("{:?}", err)),
-            Ok(_) => Ok(()),
+        let (w, h) = (image.width(), image.height());
+        let image = match image_source(image) {
+            Some(image) => image,
+            None => return,
         };
+        let (transform, dest) = options.place(transform, dest.unwrap_or([0.0, 0.0, w, h]));
+        self.apply_transform(transform);
+        //透明度、混合方式和采样方式在restore时恢复
+        let operation = match options.blend {
+            BlendMode::Normal => "source-over",
+            BlendMode::Additive => "lighter",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+        };
+        let smoothing = options.sampling.unwrap_or(self.sampling) == Sampling::Linear;
+        js! {
+            @(no_return)
+            var ctx = @{&self.context};
+            ctx.globalAlpha = @{options.opacity()};
+            ctx.globalCompositeOperation = @{operation};
+            ctx.imageSmoothingEnabled = @{smoothing};
+        }
+
+        let [sx, sy, sw, sh] = src.unwrap_or([0.0, 0.0, w, h]);
+        let [dx, dy, dw, dh] = dest;
+        let tint = options.tint;
+        if tint[0..3] != [255, 255, 255] {
+            //在临时canvas中将图片和tint颜色相乘，再用图片的alpha裁剪
+            let color = format!("rgb({},{},{})", tint[0], tint[1], tint[2]);
+            js! {
+                @(no_return)
+                var image = @{image};
+                var sx = @{sx}, sy = @{sy}, sw = @{sw}, sh = @{sh};
+                var canvas = window.mengineTintCanvas;
+                if (!canvas) {
+                    canvas = window.mengineTintCanvas = document.createElement("canvas");
+                }
+                canvas.width = Math.max(1, Math.ceil(sw));
+                canvas.height = Math.max(1, Math.ceil(sh));
+                var tmp = canvas.getContext("2d");
+                tmp.drawImage(image, sx, sy, sw, sh, 0, 0, sw, sh);
+                tmp.globalCompositeOperation = "multiply";
+                tmp.fillStyle = @{color};
+                tmp.fillRect(0, 0, sw, sh);
+                tmp.globalCompositeOperation = "destination-in";
+                tmp.drawImage(image, sx, sy, sw, sh, 0, 0, sw, sh);
+                @{&self.context}.drawImage(canvas, 0, 0, sw, sh, @{dx}, @{dy}, @{dw}, @{dh});
+            }
+            self.context.restore();
+            return;
+        }
+
+        js! {
+            @(no_return)
+            @{&self.context}.drawImage(@{image}, @{sx}, @{sy}, @{sw}, @{sh}, @{dx}, @{dy}, @{dw}, @{dh});
+        }
         self.context.restore();
     }
 
@@ -287,14 +513,173 @@ impl Graphics for BrowserGraphics {
             .set_text_baseline(stdweb::web::TextBaseline::Top);
         self.context.fill_text(cotnent, x, y, None);
     }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.context.set_fill_style_color(&format!(
+            "rgba({},{},{},{})",
+            color[0],
+            color[1],
+            color[2],
+            color[3] as f64 / 255.0
+        ));
+        self.build_path(points, true);
+        self.context.fill(stdweb::web::FillRule::NonZero);
+    }
+
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        self.set_stroke_style(color, style);
+        self.build_path(points, closed);
+        self.context.stroke();
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        self.context.set_fill_style_color(&format!(
+            "rgba({},{},{},{})",
+            color[0],
+            color[1],
+            color[2],
+            color[3] as f64 / 255.0
+        ));
+        self.build_commands(path);
+        self.context.fill(match rule {
+            FillRule::NonZero => stdweb::web::FillRule::NonZero,
+            FillRule::EvenOdd => stdweb::web::FillRule::EvenOdd,
+        });
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        self.set_stroke_style(color, style);
+        self.build_commands(path);
+        self.context.stroke();
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.set_fill_brush(brush);
+        self.build_path(points, true);
+        self.context.fill(stdweb::web::FillRule::NonZero);
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        self.set_fill_brush(brush);
+        self.build_commands(path);
+        self.context.fill(match rule {
+            FillRule::NonZero => stdweb::web::FillRule::NonZero,
+            FillRule::EvenOdd => stdweb::web::FillRule::EvenOdd,
+        });
+    }
+
+    fn save(&mut self) {
+        self.stack.push(self.transform);
+        self.context.save();
+    }
+
+    fn restore(&mut self) {
+        if let Some(transform) = self.stack.pop() {
+            self.transform = transform;
+            self.context.restore();
+        }
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.transform = transform;
+        let m = transform.then(&self.base_transform);
+        self.context.set_transform(m.a, m.b, m.c, m.d, m.e, m.f);
+    }
+
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        self.clips += 1;
+        self.context.save();
+        self.context.begin_path();
+        self.context.rect(rect[0], rect[1], rect[2], rect[3]);
+        self.context.clip(stdweb::web::FillRule::NonZero);
+    }
+
+    fn pop_clip(&mut self) {
+        if self.clips > 0 {
+            self.clips -= 1;
+            self.context.restore();
+            //restore会同时恢复push_clip之后修改的变换
+            self.set_transform(self.transform);
+        }
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        let canvas = self.context.get_canvas();
+        let (width, height) = (canvas.width(), canvas.height());
+        let data: TypedArray<u8> = js! {
+            var ctx = @{&self.context};
+            var image_data = ctx.getImageData(0, 0, @{width}, @{height});
+            return new Uint8Array(image_data.data.buffer);
+        }
+        .try_into()
+        .ok()?;
+        Some(ImageData::new(width, height, data.to_vec()))
+    }
+}
+
+/// 离屏画布，内容复制到新的<canvas>元素
+impl NativeCanvas for BrowserGraphics {
+    fn clear(&mut self, color: &[u8; 4]) {
+        self.reset_state(Matrix2D::IDENTITY);
+        let canvas = self.context.get_canvas();
+        let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+        self.context.clear_rect(0.0, 0.0, width, height);
+        self.fill_rect(color, 0.0, 0.0, width, height);
+    }
+
+    fn to_image(&self) -> Image {
+        let canvas = self.context.get_canvas();
+        let copy = create_canvas_element(canvas.width(), canvas.height());
+        js! {
+            @(no_return)
+            @{&copy}.getContext("2d").drawImage(@{&canvas}, 0, 0);
+        }
+        Image::new(copy)
+    }
+}
+
+/// 浏览器后端，使用canvas绘图，Web Audio播放声音
+pub struct BrowserBackend;
+
+impl Backend for BrowserBackend {
+    type Image = ImageElement;
+    type Sound = stdweb::Value;
+    type Window = BrowserWindow;
+    type Graphics = BrowserGraphics;
+
+    fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+        run::<S>(title, width, height, settings);
+    }
+
+    fn play_sound(sound: &stdweb::Value) {
+        play_sound(sound);
+    }
 }
 
-pub fn play_sound(sound: &Sound) {
+fn play_sound(sound: &stdweb::Value) {
     js! {
         try{
                 var audioCtx = window.audioContext;
                 var source = audioCtx.createBufferSource();
-                source.buffer = @{sound.buffer.clone()};
+                source.buffer = @{sound.clone()};
                 source.connect(audioCtx.destination);
                 source.start(0);
             }catch(e){
@@ -329,7 +714,7 @@ struct HeaderPart<'a> {
 #[template(path = "body_part.html")]
 struct BodyPart {}
 
-pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
     stdweb::initialize();
     let body = BodyPart {};
     let _ = document()
@@ -352,16 +737,26 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
         fps_count: 0,
         ups: 0,
         fps: 0,
-        ups_fps_timer: AnimationTimer::new(1.0),
-        timer: AnimationTimer::new(settings.ups as f64),
+        next_log_time: current_timestamp() + 1000.0,
+        timestep: FixedTimestep::new(settings.ups, settings.max_updates_per_frame),
+        cursor_visible: settings.show_cursor,
+        cursor_icon: CursorIcon::Default,
+        quit: false,
+        scale_factor: device_pixel_ratio(),
+        sampling: settings.sampling,
     }));
+    game_window.borrow().update_cursor();
 
     let context: CanvasRenderingContext2d = canvas.get_context().unwrap();
-    let mut graphics = BrowserGraphics { context: context };
+    let mut graphics = BrowserGraphics::new(context, settings.sampling);
 
     let game_state = Rc::new(RefCell::new(S::new(&mut *game_window.borrow_mut())));
-    let (trans_x, trans_y) = (Rc::new(RefCell::new(0.0)), Rc::new(RefCell::new(0.0)));
-    let (scale_x, scale_y) = (Rc::new(RefCell::new(1.0)), Rc::new(RefCell::new(1.0)));
+    let viewport = Rc::new(RefCell::new(Viewport::new(
+        settings.scale_mode,
+        (width, height),
+        (width, height),
+        game_window.borrow().scale_factor,
+    )));
 
     //声音播放
     js! {
@@ -374,7 +769,7 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
 
     //获取icon文件路径和扩展名
     let (icon_path, icon_type) = if let Some(icon) = settings.icon_path {
-        let icon_path = Path::new(icon);
+        let icon_path = std::path::Path::new(icon);
         let mut icon_type = "*";
         if let Some(ext) = icon_path.extension() {
             if ext == "ico" {
@@ -399,18 +794,7 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
 
     let window = stdweb::web::window();
 
-    canvas.set_width(window.inner_width() as u32);
-    canvas.set_height(window.inner_height() as u32);
-    canvas
-        .set_attribute(
-            "style",
-            &format!(
-                "width:{}px;height:{}px;",
-                window.inner_width(),
-                window.inner_height()
-            ),
-        )
-        .unwrap();
+    resize_canvas(&canvas);
     document().set_title(title);
 
     //随窗口更改canvas大小
@@ -421,17 +805,7 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
             .unwrap()
             .try_into()
             .unwrap();
-        let window = stdweb::web::window();
-        canvas.set_width(window.inner_width() as u32);
-        canvas.set_height(window.inner_height() as u32);
-        let _ = canvas.set_attribute(
-            "style",
-            &format!(
-                "width:{}px;height:{}px",
-                window.inner_width(),
-                window.inner_height()
-            ),
-        );
+        resize_canvas(&canvas);
     });
 
     // -------------- 更新函数部分 --------------------
@@ -441,26 +815,30 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
 
     let callback = move || {
         let mut w = gw.borrow_mut();
+        if w.quit {
+            return;
+        }
 
-        if w.timer.ready_for_next_frame() {
-            s_update.borrow_mut().update(&mut *w);
+        let updates = w.timestep.advance(current_timestamp());
+        for _ in 0..updates {
+            let time = w.timestep.step();
+            s_update.borrow_mut().update(&mut *w, time);
             w.ups_count += 1;
+        }
+        if updates > 0 {
             if let Ok((path, t, result)) = receiver.try_recv() {
                 match result {
                     Ok(RawAssets::Image(image)) => s_update.borrow_mut().on_assets_load(
                         &path,
                         AssetsType::Image,
-                        Ok(Assets::Image(Image { image })),
+                        Ok(Assets::Image(Image::new(image))),
                         &mut *w,
                     ),
                     Ok(RawAssets::Value(data)) => {
                         s_update.borrow_mut().on_assets_load(
                             &path,
                             t,
-                            Ok(Assets::Sound(Sound {
-                                buffer: data,
-                                audio_type: AudioType::test(&path),
-                            })),
+                            Ok(Assets::Sound(Sound::new(AudioType::test(&path), data))),
                             &mut *w,
                         );
                     }
@@ -481,7 +859,8 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
             }
         }
 
-        if w.ups_fps_timer.ready_for_next_frame() {
+        if current_timestamp() >= w.next_log_time {
+            w.next_log_time += 1000.0;
             w.ups = w.ups_count;
             w.fps = w.fps_count;
             w.ups_count = 0;
@@ -491,58 +870,44 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
 
     start_update_loop(callback);
 
-    let (auto_scale, draw_center) = (settings.auto_scale, settings.draw_center);
     let background_color = settings.background_color.unwrap_or([0, 0, 0, 255]);
-    let (tx_clone, ty_clone, sx_clone, sy_clone) = (
-        trans_x.clone(),
-        trans_y.clone(),
-        scale_x.clone(),
-        scale_y.clone(),
-    );
+    let letterbox_color = settings.letterbox_color.unwrap_or(background_color);
+    let viewport_clone = viewport.clone();
 
     let winclone = game_window.clone();
     let state_anim = game_state.clone();
     let mut animation_fn = move |_timestamp| {
+        if winclone.borrow().quit {
+            return;
+        }
         winclone.borrow_mut().fps_count += 1;
-        let (window_width, window_height) =
-            (window.inner_width() as f64, window.inner_height() as f64);
+        //canvas按照物理像素绘制
+        let scale_factor = winclone.borrow().scale_factor;
+        let (window_width, window_height) = (
+            window.inner_width() as f64 * scale_factor,
+            window.inner_height() as f64 * scale_factor,
+        );
         let mut state = state_anim.borrow_mut();
+        graphics.reset_state(Matrix2D::IDENTITY);
         graphics.fill_rect(&background_color, 0., 0., window_width, window_height);
-        graphics.context.save();
-
-        let (mut new_width, mut new_height) = (width, height);
-        let (mut scale_x, mut scale_y) = (1.0, 1.0);
-        if auto_scale {
-            //画面不超过窗口高度
-            new_height = window_height;
-            new_width = new_height / height * width;
-
-            if new_width > window_width {
-                new_width = window_width;
-                new_height = new_width / width * height;
-            }
-            scale_x = new_width / width;
-            scale_y = new_height / height;
-            graphics.context.scale(scale_x, scale_y);
-            *sx_clone.borrow_mut() = scale_x;
-            *sy_clone.borrow_mut() = scale_y;
-        }
-        let (mut trans_x, mut trans_y) = (0.0, 0.0);
-        if draw_center {
-            trans_x = (window_width - new_width) / 2.;
-            trans_y = (window_height - new_height) / 2.;
-            graphics
-                .context
-                .translate(trans_x / scale_x, trans_y / scale_y);
-            *tx_clone.borrow_mut() = trans_x;
-            *ty_clone.borrow_mut() = trans_y;
-        }
-
-        graphics.context.save();
-        state.draw(&mut graphics, &mut *winclone.borrow_mut());
+
+        let viewport = Viewport::new(
+            settings.scale_mode,
+            (width, height),
+            (window_width, window_height),
+            scale_factor,
+        );
+        let viewport_transform = Matrix2D::scaling(viewport.scale_x, viewport.scale_y)
+            .then(&Matrix2D::translation(viewport.x, viewport.y));
+        graphics.reset_state(viewport_transform);
+        *viewport_clone.borrow_mut() = viewport;
+
+        let time = winclone.borrow().timestep.frame_time();
+        state.draw(&mut graphics, &mut *winclone.borrow_mut(), time);
 
         //显示UPS/FPS
         if settings.show_ups_fps {
+            graphics.reset_state(viewport_transform);
             let _ = graphics.draw_text(
                 &format!(
                     "UPS/FPS:{}/{}",
@@ -556,30 +921,11 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
             );
         }
 
-        graphics.context.restore();
-        if draw_center {
-            graphics.context.restore();
+        graphics.reset_state(Matrix2D::IDENTITY);
+        //遮盖画面以外的部分
+        for [x, y, w, h] in viewport.letterbox((window_width, window_height)) {
+            graphics.fill_rect(&letterbox_color, x, y, w, h);
         }
-        //遮盖上部分窗口
-        graphics.fill_rect(&background_color, 0., 0., window_width, trans_y);
-        //遮盖下部分窗口
-        graphics.fill_rect(
-            &background_color,
-            0.,
-            trans_y + height * scale_y,
-            window_width,
-            window_height - (trans_y + height * scale_y),
-        );
-        //遮盖左部分窗口
-        graphics.fill_rect(&background_color, 0.0, 0.0, trans_x, window_height);
-        //遮盖右部分窗口
-        graphics.fill_rect(
-            &background_color,
-            trans_x + width * scale_x,
-            0.0,
-            window_width - (trans_x + width * scale_x),
-            window_height,
-        );
     };
     animation_fn(0.0);
 
@@ -593,60 +939,48 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
     };
 
     let s_mouse_move = game_state.clone();
-    let (tx_clone, ty_clone, sx_clone, sy_clone) = (
-        trans_x.clone(),
-        trans_y.clone(),
-        scale_x.clone(),
-        scale_y.clone(),
-    );
+    let viewport_clone = viewport.clone();
     let winclone = game_window.clone();
     canvas.add_event_listener(move |event: PointerMoveEvent| {
-        s_mouse_move.borrow_mut().event(
-            Event::MouseMove(
-                (event.offset_x() - *tx_clone.borrow()) / *sx_clone.borrow(),
-                (event.offset_y() - *ty_clone.borrow()) / *sy_clone.borrow(),
-            ),
-            &mut *winclone.borrow_mut(),
+        let scale_factor = winclone.borrow().scale_factor;
+        let (x, y) = viewport_clone.borrow().to_game(
+            event.offset_x() * scale_factor,
+            event.offset_y() * scale_factor,
         );
+        s_mouse_move
+            .borrow_mut()
+            .event(Event::MouseMove(x, y), &mut *winclone.borrow_mut());
     });
 
     let s_touch_move = game_state.clone();
     let winclone = game_window.clone();
-    let (tx_clone, ty_clone, sx_clone, sy_clone) = (
-        trans_x.clone(),
-        trans_y.clone(),
-        scale_x.clone(),
-        scale_y.clone(),
-    );
+    let viewport_clone = viewport.clone();
     canvas.add_event_listener(move |event: TouchMove| {
         let touchs = event.target_touches();
         if touchs.len() > 0 {
-            s_touch_move.borrow_mut().event(
-                Event::MouseMove(
-                    (touchs[0].client_x() - *tx_clone.borrow()) / *sx_clone.borrow(),
-                    (touchs[0].client_y() - *ty_clone.borrow()) / *sy_clone.borrow(),
-                ),
-                &mut *winclone.borrow_mut(),
+            let scale_factor = winclone.borrow().scale_factor;
+            let (x, y) = viewport_clone.borrow().to_game(
+                touchs[0].client_x() * scale_factor,
+                touchs[0].client_y() * scale_factor,
             );
+            s_touch_move
+                .borrow_mut()
+                .event(Event::MouseMove(x, y), &mut *winclone.borrow_mut());
         }
     });
 
     let s_click = game_state.clone();
     let winclone = game_window.clone();
-    let (tx_clone, ty_clone, sx_clone, sy_clone) = (
-        trans_x.clone(),
-        trans_y.clone(),
-        scale_x.clone(),
-        scale_y.clone(),
-    );
+    let viewport_clone = viewport.clone();
     canvas.add_event_listener(move |event: ClickEvent| {
-        s_click.borrow_mut().event(
-            Event::Click(
-                (event.offset_x() - *tx_clone.borrow()) / *sx_clone.borrow(),
-                (event.offset_y() - *ty_clone.borrow()) / *sy_clone.borrow(),
-            ),
-            &mut *winclone.borrow_mut(),
+        let scale_factor = winclone.borrow().scale_factor;
+        let (x, y) = viewport_clone.borrow().to_game(
+            event.offset_x() * scale_factor,
+            event.offset_y() * scale_factor,
         );
+        s_click
+            .borrow_mut()
+            .event(Event::Click(x, y), &mut *winclone.borrow_mut());
     });
 
     canvas.add_event_listener(move |_event: MouseDownEvent| {
@@ -680,6 +1014,156 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
             &mut *winclone.borrow_mut(),
         );
     });
+
+    let s_resize = game_state.clone();
+    let winclone = game_window.clone();
+    stdweb::web::window().add_event_listener(move |_event: ResizeEvent| {
+        let window = stdweb::web::window();
+        let mut w = winclone.borrow_mut();
+        //浏览器缩放或者移动到另一个显示器时devicePixelRatio会改变
+        let scale_factor = device_pixel_ratio();
+        if scale_factor != w.scale_factor {
+            w.scale_factor = scale_factor;
+            s_resize
+                .borrow_mut()
+                .event(Event::ScaleFactorChanged(scale_factor), &mut *w);
+        }
+        s_resize.borrow_mut().on_resize(
+            window.inner_width() as f64,
+            window.inner_height() as f64,
+            &mut *w,
+        );
+    });
+
+    let s_focus = game_state.clone();
+    let winclone = game_window.clone();
+    stdweb::web::window().add_event_listener(move |_event: FocusEvent| {
+        s_focus
+            .borrow_mut()
+            .on_focus_changed(true, &mut *winclone.borrow_mut());
+    });
+    let s_blur = game_state.clone();
+    let winclone = game_window.clone();
+    stdweb::web::window().add_event_listener(move |_event: BlurEvent| {
+        s_blur
+            .borrow_mut()
+            .on_focus_changed(false, &mut *winclone.borrow_mut());
+    });
+
+    //页面切换到后台时挂起
+    let s_visibility = game_state.clone();
+    let winclone = game_window.clone();
+    let on_visibility_change = move |hidden: bool| {
+        if hidden {
+            s_visibility
+                .borrow_mut()
+                .on_suspend(&mut *winclone.borrow_mut());
+        } else {
+            s_visibility
+                .borrow_mut()
+                .on_resume(&mut *winclone.borrow_mut());
+        }
+    };
+    js! {
+        var on_visibility_change = @{on_visibility_change};
+        document.addEventListener("visibilitychange", function(){
+            on_visibility_change(document.hidden);
+        });
+    };
+
+    //关闭页面前询问State，返回false时浏览器弹出确认对话框
+    let s_exit = game_state.clone();
+    let winclone = game_window.clone();
+    let on_exit = move || -> bool { s_exit.borrow_mut().on_exit(&mut *winclone.borrow_mut()) };
+    js! {
+        var on_exit = @{on_exit};
+        window.addEventListener("beforeunload", function(event){
+            if (!on_exit()) {
+                event.preventDefault();
+                event.returnValue = "";
+            }
+        });
+    };
+}
+
+/// 屏幕缩放比例(物理像素/CSS像素)
+fn device_pixel_ratio() -> f64 {
+    let ratio = js! {
+        return window.devicePixelRatio || 1;
+    };
+    ratio.try_into().unwrap_or(1.0)
+}
+
+/// Image中的<img>或者离屏画布的<canvas>
+fn image_source(image: &Image) -> Option<&stdweb::Reference> {
+    if let Some(image) = image.native::<ImageElement>() {
+        Some(image.as_ref())
+    } else {
+        image
+            .native::<CanvasElement>()
+            .map(|canvas| canvas.as_ref())
+    }
+}
+
+/// 渐变节点的位置和CSS颜色
+fn gradient_stops(stops: &[GradientStop]) -> (Vec<f64>, Vec<String>) {
+    stops
+        .iter()
+        .map(|stop| {
+            let c = stop.color;
+            (
+                stop.offset,
+                format!("rgba({},{},{},{})", c[0], c[1], c[2], c[3] as f64 / 255.0),
+            )
+        })
+        .unzip()
+}
+
+/// 创建不添加到页面中的<canvas>元素
+fn create_canvas_element(width: u32, height: u32) -> CanvasElement {
+    let canvas: CanvasElement = document()
+        .create_element("canvas")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    canvas.set_width(width);
+    canvas.set_height(height);
+    canvas
+}
+
+/// canvas按照物理像素设置大小，显示大小和浏览器窗口一致
+fn resize_canvas(canvas: &CanvasElement) {
+    let window = stdweb::web::window();
+    let scale_factor = device_pixel_ratio();
+    canvas.set_width((window.inner_width() as f64 * scale_factor) as u32);
+    canvas.set_height((window.inner_height() as f64 * scale_factor) as u32);
+    let _ = canvas.set_attribute(
+        "style",
+        &format!(
+            "width:{}px;height:{}px;",
+            window.inner_width(),
+            window.inner_height()
+        ),
+    );
+}
+
+/// CursorIcon对应的CSS光标
+fn css_cursor(icon: CursorIcon) -> &'static str {
+    match icon {
+        CursorIcon::Default => "default",
+        CursorIcon::Pointer => "pointer",
+        CursorIcon::Crosshair => "crosshair",
+        CursorIcon::Text => "text",
+        CursorIcon::Wait => "wait",
+        CursorIcon::Progress => "progress",
+        CursorIcon::Help => "help",
+        CursorIcon::Move => "move",
+        CursorIcon::NotAllowed => "not-allowed",
+        CursorIcon::Grab => "grab",
+        CursorIcon::Grabbing => "grabbing",
+        CursorIcon::EwResize => "ew-resize",
+        CursorIcon::NsResize => "ns-resize",
+    }
 }
 
 /// 在worker中使用interval加速callback调用频率(目前限制最快1000ups)
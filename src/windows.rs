@@ -1,48 +1,310 @@
-use super::{Assets, AssetsType, AudioType, Event, Graphics, Settings, State, Transform, Window};
-use direct2d::brush::SolidColorBrush;
+use super::desktop::{apply_command, build_window, key_event, mouse_event};
+use super::shape;
+use super::{
+    Assets, AssetsType, AudioType, Backend, BlendMode, Brush, Canvas, CursorIcon, DrawOptions,
+    Event, FillRule, FixedTimestep, GradientStop, Graphics, Image, ImageData, Matrix2D,
+    NativeCanvas, NativeImage, Path, Sampling, Settings, Sound, State, StrokeStyle, Viewport,
+    Window, WindowCommand,
+};
+use com_wrapper::ComWrapper;
+use direct2d::brush::{BitmapBrush, LinearGradientBrush, RadialGradientBrush, SolidColorBrush};
 use direct2d::enums::{
-    BitmapInterpolationMode, DrawTextOptions, PresentOptions, RenderTargetType, RenderTargetUsage,
+    AntialiasMode, BitmapInterpolationMode, DrawTextOptions, ExtendMode, FigureBegin, FigureEnd,
+    FillMode, PresentOptions, RenderTargetType, RenderTargetUsage,
 };
 use direct2d::image::Bitmap;
 use direct2d::render_target::hwnd::HwndRenderTarget;
+use direct2d::render_target::RenderTarget;
 use directwrite::text_format::TextFormat;
 use dxgi::enums::*;
 use image::RgbaImage;
 use math2d::*;
 use rodio::Source;
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 use std::io::{BufReader, Cursor, Error, ErrorKind, Result};
-use std::rc::Rc;
+use std::ops::{Deref, DerefMut};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
+use std::{mem, ptr, slice};
+use winapi::shared::dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM;
+use winapi::shared::guiddef::GUID;
+use winapi::shared::minwindef::TRUE;
 use winapi::shared::windef::HWND;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::d2d1::{
+    D2D1CreateFactory, ID2D1Bitmap, ID2D1BitmapRenderTarget, ID2D1Factory, ID2D1Geometry,
+    ID2D1Image, ID2D1Layer, ID2D1RenderTarget, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+    D2D1_BITMAP_PROPERTIES, D2D1_COLOR_F, D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE,
+    D2D1_DEBUG_LEVEL_NONE, D2D1_FACTORY_OPTIONS, D2D1_FACTORY_TYPE_MULTI_THREADED,
+    D2D1_LAYER_OPTIONS_NONE, D2D1_LAYER_PARAMETERS,
+};
+use winapi::um::d2d1_1::{
+    ID2D1Bitmap1, ID2D1DeviceContext, ID2D1Effect, D2D1_BITMAP_OPTIONS,
+    D2D1_BITMAP_OPTIONS_CANNOT_DRAW, D2D1_BITMAP_OPTIONS_CPU_READ, D2D1_BITMAP_OPTIONS_NONE,
+    D2D1_BITMAP_PROPERTIES1, D2D1_COMPOSITE_MODE_PLUS, D2D1_COMPOSITE_MODE_SOURCE_COPY,
+    D2D1_COMPOSITE_MODE_SOURCE_OVER, D2D1_INTERPOLATION_MODE_LINEAR,
+    D2D1_INTERPOLATION_MODE_NEAREST_NEIGHBOR, D2D1_MAPPED_RECT, D2D1_MAP_OPTIONS_READ,
+    D2D1_MATRIX_5X4_F, D2D1_PROPERTY_TYPE_UNKNOWN, D2D1_VECTOR_4F,
+};
+use winapi::um::d2d1effects::{
+    CLSID_D2D12DAffineTransform, CLSID_D2D1Blend, CLSID_D2D1ColorMatrix, CLSID_D2D1Crop,
+    D2D1_2DAFFINETRANSFORM_INTERPOLATION_MODE_LINEAR,
+    D2D1_2DAFFINETRANSFORM_INTERPOLATION_MODE_NEAREST_NEIGHBOR,
+    D2D1_2DAFFINETRANSFORM_PROP_INTERPOLATION_MODE, D2D1_2DAFFINETRANSFORM_PROP_TRANSFORM_MATRIX,
+    D2D1_BLEND_MODE_MULTIPLY, D2D1_BLEND_MODE_SCREEN, D2D1_BLEND_PROP_MODE,
+    D2D1_COLORMATRIX_PROP_COLOR_MATRIX, D2D1_CROP_PROP_RECT,
+};
+use winapi::um::dcommon::{
+    D2D1_ALPHA_MODE_IGNORE, D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_MATRIX_3X2_F, D2D1_PIXEL_FORMAT,
+    D2D1_POINT_2F, D2D1_POINT_2U, D2D1_RECT_F, D2D1_RECT_U, D2D1_SIZE_U,
+};
+use winapi::um::unknwnbase::IUnknown;
+use winapi::Interface;
 use winit::dpi::LogicalSize;
-use winit::{ElementState, MouseButton, VirtualKeyCode};
+use wio::com::ComPtr;
+
+/// Direct2D位图
+///
+/// 不支持Direct2D 1.1特效的设备上保留原始像素，染色时在CPU上生成位图；
+/// 离屏画布转换的图片没有保留像素，这些设备上不能染色
+pub struct D2DImage {
+    bitmap: Bitmap,
+    pixels: Option<RgbaImage>,
+    //染色之后的位图，按照染色的颜色缓存
+    tinted: RefCell<HashMap<[u8; 3], Bitmap>>,
+}
 
-#[derive(Debug, Clone)]
-pub struct Sound {
-    audio_type: AudioType,
-    buffer: Vec<u8>,
+impl NativeImage for D2DImage {
+    fn width(&self) -> f64 {
+        self.bitmap.size().width as f64
+    }
+    fn height(&self) -> f64 {
+        self.bitmap.size().height as f64
+    }
+}
+
+impl D2DImage {
+    fn new(bitmap: Bitmap, pixels: Option<RgbaImage>) -> D2DImage {
+        D2DImage {
+            bitmap,
+            pixels,
+            tinted: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// 在CPU上染色之后的位图，没有保留像素时返回None
+    fn tinted(&self, target: &RenderTarget, tint: [u8; 3]) -> Option<Ref<Bitmap>> {
+        let pixels = self.pixels.as_ref()?;
+        if !self.tinted.borrow().contains_key(&tint) {
+            let mut data = pixels.clone().into_raw();
+            for pixel in data.chunks_mut(4) {
+                for (c, t) in pixel.iter_mut().zip(&tint) {
+                    *c = (*c as u32 * *t as u32 / 255) as u8;
+                }
+            }
+            let (w, h) = pixels.dimensions();
+            let bitmap = Bitmap::create(target)
+                .with_format(Format::R8G8B8A8Unorm)
+                .with_raw_data(Sizeu::new(w, h), &data, w * 4)
+                .build()
+                .ok()?;
+            let mut tinted = self.tinted.borrow_mut();
+            //颜色一直变化时不无限缓存
+            if tinted.len() >= 16 {
+                tinted.clear();
+            }
+            tinted.insert(tint, bitmap);
+        }
+        Some(Ref::map(self.tinted.borrow(), |tinted| &tinted[&tint]))
+    }
+}
+
+/// 离屏画布的绘图目标，和窗口的绘图目标兼容，可以共享位图和画刷
+struct BitmapTarget {
+    target: RenderTarget,
+    bitmap_target: ComPtr<ID2D1BitmapRenderTarget>,
+}
+
+impl Deref for BitmapTarget {
+    type Target = RenderTarget;
+    fn deref(&self) -> &RenderTarget {
+        &self.target
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct Image {
-    bitmap: Rc<Bitmap>,
-    width: f64,
-    height: f64,
+impl DerefMut for BitmapTarget {
+    fn deref_mut(&mut self) -> &mut RenderTarget {
+        &mut self.target
+    }
+}
+
+impl BitmapTarget {
+    /// 创建和parent兼容的位图绘图目标，像素格式为预乘透明度的BGRA，dpi和parent相同
+    fn create(parent: &RenderTarget, width: u32, height: u32) -> Option<BitmapTarget> {
+        let format = D2D1_PIXEL_FORMAT {
+            format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+        };
+        unsafe {
+            let parent = &*(parent.get_raw() as *mut ID2D1RenderTarget);
+            let mut raw = ptr::null_mut();
+            if !SUCCEEDED(parent.CreateCompatibleRenderTarget(
+                ptr::null(),
+                &D2D1_SIZE_U { width, height },
+                &format,
+                D2D1_COMPATIBLE_RENDER_TARGET_OPTIONS_NONE,
+                &mut raw,
+            )) {
+                return None;
+            }
+            let bitmap_target: ComPtr<ID2D1BitmapRenderTarget> = ComPtr::from_raw(raw);
+            let target =
+                RenderTarget::from_raw(bitmap_target.clone().up::<ID2D1RenderTarget>().into_raw());
+            Some(BitmapTarget {
+                target,
+                bitmap_target,
+            })
+        }
+    }
+}
+
+/// 离屏画布，绘制到位图绘图目标
+///
+/// 绘图目标一直处于begin_draw之后的状态，to_image时结束绘制，复制位图之后重新开始
+struct D2DCanvas {
+    graphics: RefCell<D2DGraphics<BitmapTarget>>,
+}
+
+impl Graphics for D2DCanvas {
+    fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
+        self.graphics
+            .get_mut()
+            .fill_rect(color, x, y, width, height);
+    }
+
+    fn draw_image_with(
+        &mut self,
+        transform: Option<Matrix2D>,
+        image: &Image,
+        src: Option<[f64; 4]>,
+        dest: Option<[f64; 4]>,
+        options: &DrawOptions,
+    ) {
+        self.graphics
+            .get_mut()
+            .draw_image_with(transform, image, src, dest, options);
+    }
+
+    fn draw_text(&mut self, cotnent: &str, x: f64, y: f64, color: &[u8; 4], font_size: u32) {
+        self.graphics
+            .get_mut()
+            .draw_text(cotnent, x, y, color, font_size);
+    }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.graphics.get_mut().fill_polygon(color, points);
+    }
+
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        self.graphics
+            .get_mut()
+            .stroke_polyline(color, points, closed, style);
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        self.graphics.get_mut().fill_path(color, path, rule);
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        self.graphics.get_mut().stroke_path(color, path, style);
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.graphics.get_mut().fill_polygon_with(brush, points);
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        self.graphics.get_mut().fill_path_with(brush, path, rule);
+    }
+
+    fn save(&mut self) {
+        self.graphics.get_mut().save();
+    }
+
+    fn restore(&mut self) {
+        self.graphics.get_mut().restore();
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.graphics.borrow().transform()
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.graphics.get_mut().set_transform(transform);
+    }
+
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        self.graphics.get_mut().push_clip(rect);
+    }
+
+    fn pop_clip(&mut self) {
+        self.graphics.get_mut().pop_clip();
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.graphics.get_mut().set_sampling(sampling);
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.graphics.borrow().sampling()
+    }
+
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        self.graphics.get_mut().capture_frame()
+    }
 }
 
-impl Image {
-    pub fn width(&self) -> f64 {
-        self.width
+impl NativeCanvas for D2DCanvas {
+    fn clear(&mut self, color: &[u8; 4]) {
+        let graphics = self.graphics.get_mut();
+        graphics.reset_state(Matrix3x2f::IDENTITY);
+        let color = to_color(color);
+        unsafe {
+            graphics.raw_target().Clear(&D2D1_COLOR_F {
+                r: color.r,
+                g: color.g,
+                b: color.b,
+                a: color.a,
+            });
+        }
+    }
+
+    fn to_image(&self) -> Image {
+        let bitmap = self
+            .graphics
+            .borrow_mut()
+            .snapshot()
+            .expect("画布转换图片失败");
+        Image::new(D2DImage::new(bitmap, None))
     }
-    pub fn height(&self) -> f64 {
-        self.height
+}
+
+impl Drop for D2DCanvas {
+    fn drop(&mut self) {
+        let graphics = self.graphics.get_mut();
+        graphics.reset_state(Matrix3x2f::IDENTITY);
+        let _ = graphics.target.end_draw();
     }
 }
 
@@ -53,12 +315,48 @@ enum RawAssets {
 
 pub struct D2DWindow {
     new_size: Option<(f64, f64)>,
+    scale_factor: f64,
     thread_sender: Sender<(String, AssetsType, Result<RawAssets>)>,
-    update_delay: Duration,
+    timestep: FixedTimestep,
+    commands: Vec<WindowCommand>,
+    quit: bool,
+    sampling: Sampling,
+    //创建离屏画布使用，和绘图线程共享同一个设备，工厂由create_factory创建，是多线程的
+    target: RenderTarget,
+    factory: direct2d::factory::Factory,
+    dwfactory: directwrite::factory::Factory,
 }
 impl Window for D2DWindow {
     fn set_update_rate(&mut self, ups: u64) {
-        self.update_delay = Duration::from_micros(1000 * 1000 / ups);
+        self.timestep.set_ups(ups);
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.commands.push(WindowCommand::Title(title.to_string()));
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.commands.push(WindowCommand::Fullscreen(fullscreen));
+    }
+
+    fn set_size(&mut self, width: f64, height: f64) {
+        self.commands.push(WindowCommand::Size(width, height));
+    }
+
+    fn set_cursor_visible(&mut self, visible: bool) {
+        self.commands.push(WindowCommand::CursorVisible(visible));
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.commands.push(WindowCommand::Cursor(icon));
+    }
+
+    fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
     }
 
     fn load_assets(&mut self, assets: &[(&str, AssetsType)]) {
@@ -123,24 +421,14 @@ impl Window for D2DWindow {
         });
     }
 
-    fn load_image(&mut self, width: u32, height: u32, key: &str, data: Vec<u8>) {
-        let image: RgbaImage = image::ImageBuffer::from_raw(width, height, data).unwrap();
-        let _ = self.thread_sender.send((
-            String::from(key),
-            AssetsType::Image,
-            Ok(RawAssets::Image(image)),
-        ));
-    }
-
-    fn load_image_bgra(&mut self, width: u32, height: u32, key: &str, data: Vec<u8>) {
-        let mut buffer = Vec::with_capacity(data.len());
-        for pixel in data.chunks(4){
-            buffer.push(pixel[2]);//r
-            buffer.push(pixel[1]);//g
-            buffer.push(pixel[0]);//b
-            buffer.push(pixel[3]);//a
-        }
-        self.load_image(width, height, key, buffer)
+    fn load_image_data(&mut self, key: &str, image: ImageData) {
+        let image = match RgbaImage::from_raw(image.width, image.height, image.data) {
+            Some(image) => Ok(RawAssets::Image(image)),
+            None => Err(Error::new(ErrorKind::InvalidData, "图片数据长度错误")),
+        };
+        let _ = self
+            .thread_sender
+            .send((String::from(key), AssetsType::Image, image));
     }
 
     fn load_svg(&mut self, key: &str, svg: String) {
@@ -155,33 +443,639 @@ impl Window for D2DWindow {
             Ok(RawAssets::Image(image)),
         ));
     }
+
+    fn create_canvas(&self, width: f64, height: f64) -> Canvas {
+        let pixels = |v: f64| v.ceil().max(1.0) as u32;
+        let target = BitmapTarget::create(&self.target, pixels(width), pixels(height))
+            .expect("创建画布失败");
+        let (factory, dwfactory) = unsafe { (share(&self.factory), share(&self.dwfactory)) };
+        let mut graphics =
+            D2DGraphics::new(target, factory, dwfactory, (width, height), self.sampling);
+        graphics.target.begin_draw();
+        let mut canvas = D2DCanvas {
+            graphics: RefCell::new(graphics),
+        };
+        canvas.clear(&[0, 0, 0, 0]);
+        Canvas::new(width, height, canvas)
+    }
 }
 
-pub struct D2DGraphics {
-    target: HwndRenderTarget,
+/// Direct2D绘图，T为窗口或者离屏画布的绘图目标
+pub struct D2DGraphics<T = HwndRenderTarget> {
+    target: T,
     text_formats: HashMap<u32, TextFormat>,
     solid_bursh: HashMap<[u8; 4], SolidColorBrush>,
     dwfactory: directwrite::factory::Factory,
+    factory: direct2d::factory::Factory,
+    //窗口的缩放和平移
+    base_transform: Matrix3x2f,
+    transform: Matrix2D,
+    stack: Vec<Matrix2D>,
+    //push_clip添加的裁剪区域，end_draw之前必须全部pop
+    clips: Vec<Clip>,
+    sampling: Sampling,
+    //游戏画面的大小
+    size: (f64, f64),
+    //染色和混合方式使用的特效，第一次使用时创建
+    effects: Option<Effects>,
+}
+
+/// 裁剪区域
+struct Clip {
+    rect: [f32; 4],
+    //push_clip时绘图目标的变换
+    transform: Matrix3x2f,
+    //旋转或斜切之后使用图层，和坐标轴对齐时为None
+    mask: Option<LayerMask>,
+}
+
+/// 裁剪使用的图层和几何遮罩
+struct LayerMask {
+    layer: ComPtr<ID2D1Layer>,
+    geometry: ComPtr<ID2D1Geometry>,
 }
 
-impl D2DGraphics {
+//图层和几何遮罩由多线程的工厂创建(见create_factory)，Direct2D内部加锁，可以在线程间转移；
+//LayerMask属于D2DGraphics，只在持有D2DGraphics锁的线程上使用
+unsafe impl Send for LayerMask {}
+
+/// Direct2D 1.1的设备上下文和特效，每次绘制重新设置输入和属性
+struct Effects {
+    context: ComPtr<ID2D1DeviceContext>,
+    color: ComPtr<ID2D1Effect>,
+    crop: ComPtr<ID2D1Effect>,
+    affine: ComPtr<ID2D1Effect>,
+    blend: ComPtr<ID2D1Effect>,
+    //复制画面使用，不够大时重新创建
+    backdrop: Option<ComPtr<ID2D1Bitmap1>>,
+}
+
+//和LayerMask一样，属于D2DGraphics，只在持有D2DGraphics锁的线程上使用
+unsafe impl Send for Effects {}
+
+impl Effects {
+    /// 设备不支持Direct2D 1.1的特效时返回None
+    unsafe fn new(target: &ID2D1RenderTarget) -> Option<Effects> {
+        let context = query_interface::<ID2D1DeviceContext>(target)?;
+        Some(Effects {
+            color: create_effect(&context, &CLSID_D2D1ColorMatrix)?,
+            crop: create_effect(&context, &CLSID_D2D1Crop)?,
+            affine: create_effect(&context, &CLSID_D2D12DAffineTransform)?,
+            blend: create_effect(&context, &CLSID_D2D1Blend)?,
+            backdrop: None,
+            context,
+        })
+    }
+}
+
+type FactoryInterface = <direct2d::factory::Factory as ComWrapper>::Interface;
+
+/// 创建多线程的Direct2D工厂
+///
+/// 绘图线程在窗口的绘图目标上begin_draw/end_draw的同时，游戏线程会创建离屏画布并在上面绘图，
+/// 多线程的工厂对它创建的所有资源的访问加锁，单线程的工厂不能这样使用
+fn create_factory() -> direct2d::factory::Factory {
+    let options = D2D1_FACTORY_OPTIONS {
+        debugLevel: D2D1_DEBUG_LEVEL_NONE,
+    };
+    unsafe {
+        let mut raw = ptr::null_mut();
+        let hr = D2D1CreateFactory(
+            D2D1_FACTORY_TYPE_MULTI_THREADED,
+            &FactoryInterface::uuidof(),
+            &options,
+            &mut raw,
+        );
+        assert!(SUCCEEDED(hr), "创建Direct2D工厂失败: {:#x}", hr);
+        direct2d::factory::Factory::from_raw(raw as *mut FactoryInterface)
+    }
+}
+
+/// 增加引用计数，返回指向同一个COM对象的包装
+unsafe fn share<T: ComWrapper>(wrapper: &T) -> T {
+    let raw = wrapper.get_raw();
+    (*(raw as *mut IUnknown)).AddRef();
+    T::from_raw(raw)
+}
+
+/// 查询COM接口，不支持时返回None
+unsafe fn query_interface<T: Interface>(unknown: &IUnknown) -> Option<ComPtr<T>> {
+    let mut raw = ptr::null_mut();
+    if SUCCEEDED(unknown.QueryInterface(&T::uuidof(), &mut raw)) {
+        Some(ComPtr::from_raw(raw as *mut T))
+    } else {
+        None
+    }
+}
+
+/// 创建Direct2D 1.1的特效
+unsafe fn create_effect(context: &ID2D1DeviceContext, id: &GUID) -> Option<ComPtr<ID2D1Effect>> {
+    let mut effect = ptr::null_mut();
+    if SUCCEEDED(context.CreateEffect(id, &mut effect)) {
+        Some(ComPtr::from_raw(effect))
+    } else {
+        None
+    }
+}
+
+/// 设置特效的属性，value的类型必须和属性的类型相同
+unsafe fn set_property<T>(effect: &ID2D1Effect, index: u32, value: &T) -> Option<()> {
+    if SUCCEEDED(effect.SetValue(
+        index,
+        D2D1_PROPERTY_TYPE_UNKNOWN,
+        value as *const T as *const u8,
+        mem::size_of::<T>() as u32,
+    )) {
+        Some(())
+    } else {
+        None
+    }
+}
+
+/// 特效的输出
+unsafe fn effect_output(effect: &ID2D1Effect) -> ComPtr<ID2D1Image> {
+    let mut output = ptr::null_mut();
+    effect.GetOutput(&mut output);
+    ComPtr::from_raw(output)
+}
+
+/// 创建Direct2D 1.1的位图，像素格式和绘图目标相同
+unsafe fn create_bitmap1(
+    context: &ID2D1DeviceContext,
+    width: u32,
+    height: u32,
+    options: D2D1_BITMAP_OPTIONS,
+) -> Option<ComPtr<ID2D1Bitmap1>> {
+    let properties = D2D1_BITMAP_PROPERTIES1 {
+        pixelFormat: context.GetPixelFormat(),
+        dpiX: 96.0,
+        dpiY: 96.0,
+        bitmapOptions: options,
+        colorContext: ptr::null(),
+    };
+    let mut bitmap = ptr::null_mut();
+    if SUCCEEDED(context.CreateBitmap(
+        D2D1_SIZE_U { width, height },
+        ptr::null(),
+        0,
+        &properties,
+        &mut bitmap,
+    )) {
+        Some(ComPtr::from_raw(bitmap))
+    } else {
+        None
+    }
+}
+
+/// Direct2D的矩阵转换为winapi的矩阵
+fn to_raw_matrix(m: &Matrix3x2f) -> D2D1_MATRIX_3X2_F {
+    D2D1_MATRIX_3X2_F {
+        matrix: [[m.a, m.b], [m.c, m.d], [m.x, m.y]],
+    }
+}
+
+/// Matrix2D转换为Direct2D的矩阵
+fn to_matrix3x2f(m: &Matrix2D) -> Matrix3x2f {
+    Matrix3x2f {
+        a: m.a as f32,
+        b: m.b as f32,
+        c: m.c as f32,
+        d: m.d as f32,
+        x: m.e as f32,
+        y: m.f as f32,
+    }
+}
+
+/// [r, g, b, a]转换为Direct2D的颜色
+fn to_color(color: &[u8; 4]) -> Color {
+    Color::new(
+        color[0] as f32 / 255.0,
+        color[1] as f32 / 255.0,
+        color[2] as f32 / 255.0,
+        color[3] as f32 / 255.0,
+    )
+}
+
+/// 渐变节点转换为Direct2D的GradientStop
+fn gradient_stops(stops: &[GradientStop]) -> Vec<direct2d::brush::gradient::GradientStop> {
+    stops
+        .iter()
+        .map(|stop| direct2d::brush::gradient::GradientStop {
+            position: stop.offset as f32,
+            color: to_color(&stop.color),
+        })
+        .collect()
+}
+
+impl<T: DerefMut<Target = RenderTarget>> D2DGraphics<T> {
+    fn new(
+        target: T,
+        factory: direct2d::factory::Factory,
+        dwfactory: directwrite::factory::Factory,
+        size: (f64, f64),
+        sampling: Sampling,
+    ) -> D2DGraphics<T> {
+        D2DGraphics {
+            solid_bursh: HashMap::new(),
+            text_formats: HashMap::new(),
+            target,
+            dwfactory,
+            factory,
+            base_transform: Matrix3x2f::IDENTITY,
+            transform: Matrix2D::IDENTITY,
+            stack: vec![],
+            clips: vec![],
+            sampling,
+            size,
+            effects: None,
+        }
+    }
+
+    fn raw_target(&self) -> &ID2D1RenderTarget {
+        unsafe { &*((*self.target).get_raw() as *mut ID2D1RenderTarget) }
+    }
+
+    /// 复制绘图目标上rect范围的像素，需要Direct2D 1.1(Windows 8，或者安装了平台更新的Windows 7)
+    fn read_pixels(&self, rect: D2D1_RECT_U) -> Option<ImageData> {
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let target = self.raw_target();
+        unsafe {
+            let context = query_interface::<ID2D1DeviceContext>(target)?;
+            let format = target.GetPixelFormat();
+            let bitmap = create_bitmap1(
+                &context,
+                width,
+                height,
+                D2D1_BITMAP_OPTIONS_CPU_READ | D2D1_BITMAP_OPTIONS_CANNOT_DRAW,
+            )?;
+            //先提交还没有执行的绘图命令
+            target.Flush(ptr::null_mut(), ptr::null_mut());
+            let point = D2D1_POINT_2U { x: 0, y: 0 };
+            let target_ptr = target as *const ID2D1RenderTarget as *mut ID2D1RenderTarget;
+            if !SUCCEEDED(bitmap.CopyFromRenderTarget(&point, target_ptr, &rect)) {
+                return None;
+            }
+            let mut mapped = D2D1_MAPPED_RECT {
+                pitch: 0,
+                bits: ptr::null(),
+            };
+            if !SUCCEEDED(bitmap.Map(D2D1_MAP_OPTIONS_READ, &mut mapped)) {
+                return None;
+            }
+            let row_size = width as usize * 4;
+            let mut data = Vec::with_capacity(row_size * height as usize);
+            for y in 0..height as usize {
+                let row = mapped.bits.add(y * mapped.pitch as usize);
+                data.extend_from_slice(slice::from_raw_parts(row, row_size));
+            }
+            bitmap.Unmap();
+            //绘图目标的像素是BGRA，透明度为预乘或者忽略
+            for pixel in data.chunks_mut(4) {
+                let alpha = pixel[3] as u32;
+                if format.alphaMode == D2D1_ALPHA_MODE_IGNORE {
+                    pixel[3] = 255;
+                } else if alpha > 0 && alpha < 255 {
+                    for c in &mut pixel[..3] {
+                        *c = (*c as u32 * 255 / alpha).min(255) as u8;
+                    }
+                }
+            }
+            Some(ImageData::from_bgra(width, height, data))
+        }
+    }
+
+    /// 清空save保存的状态和裁剪区域，base为窗口的缩放和平移
+    fn reset_state(&mut self, base: Matrix3x2f) {
+        while !self.clips.is_empty() {
+            self.pop_clip();
+        }
+        self.base_transform = base;
+        self.transform = Matrix2D::IDENTITY;
+        self.stack.clear();
+        self.target.set_transform(&base);
+    }
+
     fn check_brush(&mut self, color: &[u8; 4]) {
         if !self.solid_bursh.contains_key(color) {
             self.solid_bursh.insert(
                 *color,
                 SolidColorBrush::create(&self.target)
-                    .with_color(Color::new(
-                        color[0] as f32 / 255.0,
-                        color[1] as f32 / 255.0,
-                        color[2] as f32 / 255.0,
-                        color[3] as f32 / 255.0,
-                    ))
+                    .with_color(to_color(color))
                     .build()
                     .unwrap(),
             );
         }
     }
 
+    /// 创建裁剪使用的图层，遮罩为当前变换中的矩形
+    fn create_layer_mask(&self, rect: [f32; 4]) -> Option<LayerMask> {
+        let [left, top, right, bottom] = rect;
+        unsafe {
+            let factory = &*(self.factory.get_raw() as *mut ID2D1Factory);
+            let mut geometry = ptr::null_mut();
+            if !SUCCEEDED(factory.CreateRectangleGeometry(
+                &D2D1_RECT_F {
+                    left,
+                    top,
+                    right,
+                    bottom,
+                },
+                &mut geometry,
+            )) {
+                return None;
+            }
+            let geometry = ComPtr::from_raw(geometry as *mut ID2D1Geometry);
+            let mut layer = ptr::null_mut();
+            if !SUCCEEDED(self.raw_target().CreateLayer(ptr::null(), &mut layer)) {
+                return None;
+            }
+            Some(LayerMask {
+                layer: ComPtr::from_raw(layer),
+                geometry,
+            })
+        }
+    }
+
+    /// 在绘图目标上添加裁剪区域，使用绘图目标当前的变换
+    fn apply_clip(&mut self, clip: &Clip) {
+        match &clip.mask {
+            None => self
+                .target
+                .push_axis_aligned_clip(clip.rect, AntialiasMode::Aliased),
+            Some(mask) => unsafe {
+                let infinite = std::f32::MAX;
+                let parameters = D2D1_LAYER_PARAMETERS {
+                    contentBounds: D2D1_RECT_F {
+                        left: -infinite,
+                        top: -infinite,
+                        right: infinite,
+                        bottom: infinite,
+                    },
+                    geometricMask: mask.geometry.as_raw(),
+                    maskAntialiasMode: D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+                    maskTransform: to_raw_matrix(&Matrix3x2f::IDENTITY),
+                    opacity: 1.0,
+                    opacityBrush: ptr::null_mut(),
+                    layerOptions: D2D1_LAYER_OPTIONS_NONE,
+                };
+                self.raw_target()
+                    .PushLayer(&parameters, mask.layer.as_raw());
+            },
+        }
+    }
+
+    /// 从绘图目标上移除裁剪区域
+    fn remove_clip(&mut self, clip: &Clip) {
+        match clip.mask {
+            None => self.target.pop_axis_aligned_clip(),
+            Some(_) => unsafe { self.raw_target().PopLayer() },
+        }
+    }
+
+    /// 暂时移除所有裁剪区域，复制绘图目标之前调用
+    fn suspend_clips(&mut self) -> Vec<Clip> {
+        let clips = std::mem::take(&mut self.clips);
+        for clip in clips.iter().rev() {
+            self.remove_clip(clip);
+        }
+        clips
+    }
+
+    /// 按照添加时的变换恢复suspend_clips移除的裁剪区域
+    fn resume_clips(&mut self, clips: Vec<Clip>) {
+        let transform = self.target.transform();
+        for clip in &clips {
+            self.target.set_transform(&clip.transform);
+            self.apply_clip(clip);
+        }
+        self.target.set_transform(&transform);
+        self.clips = clips;
+    }
+
+    /// 设备是否支持Direct2D 1.1的特效
+    fn supports_effects(&self) -> bool {
+        unsafe { query_interface::<ID2D1DeviceContext>(self.raw_target()).is_some() }
+    }
+
+    /// 使用Direct2D 1.1的特效绘制染色和混合方式，设备不支持时返回None
+    ///
+    /// transform为图片的变换和当前变换，src的左上角对应dest的左上角
+    fn draw_effect(
+        &mut self,
+        bitmap: &Bitmap,
+        options: &DrawOptions,
+        src: Rectf,
+        dest: Rectf,
+        transform: Matrix3x2f,
+        sampling: Sampling,
+    ) -> Option<()> {
+        let (sw, sh) = (src.right - src.left, src.bottom - src.top);
+        if sw <= 0.0 || sh <= 0.0 {
+            return Some(());
+        }
+        let mapping = Matrix3x2f::scaling(
+            [(dest.right - dest.left) / sw, (dest.bottom - dest.top) / sh],
+            [0.0, 0.0],
+        ) * Matrix3x2f::translation([dest.left, dest.top])
+            * transform;
+        let src = D2D1_RECT_F {
+            left: src.left,
+            top: src.top,
+            right: src.right,
+            bottom: src.bottom,
+        };
+        unsafe {
+            let mut effects = match self.effects.take() {
+                Some(effects) => effects,
+                None => Effects::new(self.raw_target())?,
+            };
+            let drawn = self.draw_tinted(&mut effects, bitmap, options, src, mapping, sampling);
+            self.effects = Some(effects);
+            drawn
+        }
+    }
+
+    /// 使用缓存的特效绘制，mapping把src变换到绘图目标上
+    unsafe fn draw_tinted(
+        &mut self,
+        effects: &mut Effects,
+        bitmap: &Bitmap,
+        options: &DrawOptions,
+        src: D2D1_RECT_F,
+        mapping: Matrix3x2f,
+        sampling: Sampling,
+    ) -> Option<()> {
+        //ColorMatrix在非预乘的颜色上计算，同时处理染色和不透明度
+        let color = &effects.color;
+        let mut matrix = D2D1_MATRIX_5X4_F {
+            matrix: [[0.0; 4]; 5],
+        };
+        for i in 0..3 {
+            matrix.matrix[i][i] = options.tint[i] as f32 / 255.0;
+        }
+        matrix.matrix[3][3] = options.opacity() as f32;
+        set_property(color, D2D1_COLORMATRIX_PROP_COLOR_MATRIX, &matrix)?;
+        color.SetInput(0, bitmap.get_raw() as *const ID2D1Image, TRUE);
+        let tinted = effect_output(color);
+        let composite = match options.blend {
+            BlendMode::Normal => D2D1_COMPOSITE_MODE_SOURCE_OVER,
+            BlendMode::Additive => D2D1_COMPOSITE_MODE_PLUS,
+            BlendMode::Multiply | BlendMode::Screen => {
+                if self
+                    .draw_blend(effects, &tinted, src, mapping, options.blend, sampling)
+                    .is_some()
+                {
+                    return Some(());
+                }
+                //不能复制画面时按Normal绘制
+                D2D1_COMPOSITE_MODE_SOURCE_OVER
+            }
+        };
+        let interpolation = match sampling {
+            Sampling::Nearest => D2D1_INTERPOLATION_MODE_NEAREST_NEIGHBOR,
+            Sampling::Linear => D2D1_INTERPOLATION_MODE_LINEAR,
+        };
+        self.target.set_transform(&mapping);
+        effects
+            .context
+            .DrawImage(&*tinted, ptr::null(), &src, interpolation, composite);
+        Some(())
+    }
+
+    /// 复制图片覆盖范围内的画面，和图片按照Multiply或Screen混合之后覆盖回去
+    ///
+    /// Direct2D的合成模式没有这两种混合方式，只能使用Blend特效
+    unsafe fn draw_blend(
+        &mut self,
+        effects: &mut Effects,
+        image: &ID2D1Image,
+        src: D2D1_RECT_F,
+        mapping: Matrix3x2f,
+        blend: BlendMode,
+        sampling: Sampling,
+    ) -> Option<()> {
+        let context = &*effects.context;
+        //裁剪出src，再变换到画面上的位置(像素)
+        let rect = D2D1_VECTOR_4F {
+            x: src.left,
+            y: src.top,
+            z: src.right,
+            w: src.bottom,
+        };
+        set_property(&effects.crop, D2D1_CROP_PROP_RECT, &rect)?;
+        effects.crop.SetInput(0, image, TRUE);
+        let interpolation = match sampling {
+            Sampling::Nearest => D2D1_2DAFFINETRANSFORM_INTERPOLATION_MODE_NEAREST_NEIGHBOR,
+            Sampling::Linear => D2D1_2DAFFINETRANSFORM_INTERPOLATION_MODE_LINEAR,
+        };
+        set_property(
+            &effects.affine,
+            D2D1_2DAFFINETRANSFORM_PROP_INTERPOLATION_MODE,
+            &interpolation,
+        )?;
+        let transform = Matrix3x2f::translation([-src.left, -src.top]) * mapping;
+        set_property(
+            &effects.affine,
+            D2D1_2DAFFINETRANSFORM_PROP_TRANSFORM_MATRIX,
+            &to_raw_matrix(&transform),
+        )?;
+        effects
+            .affine
+            .SetInput(0, &*effect_output(&effects.crop), TRUE);
+        let source = effect_output(&effects.affine);
+
+        //只复制和覆盖图片所在的范围
+        let mut bounds = D2D1_RECT_F {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        };
+        if !SUCCEEDED(context.GetImageLocalBounds(&*source, &mut bounds)) {
+            return None;
+        }
+        let size = context.GetPixelSize();
+        let rect = D2D1_RECT_U {
+            left: bounds.left.floor().max(0.0) as u32,
+            top: bounds.top.floor().max(0.0) as u32,
+            right: (bounds.right.ceil().max(0.0) as u32).min(size.width),
+            bottom: (bounds.bottom.ceil().max(0.0) as u32).min(size.height),
+        };
+        if rect.left >= rect.right || rect.top >= rect.bottom {
+            return Some(());
+        }
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+        let capacity = match &effects.backdrop {
+            Some(backdrop) => backdrop.GetPixelSize(),
+            None => D2D1_SIZE_U {
+                width: 0,
+                height: 0,
+            },
+        };
+        if capacity.width < width || capacity.height < height {
+            effects.backdrop = Some(create_bitmap1(
+                context,
+                capacity.width.max(width),
+                capacity.height.max(height),
+                D2D1_BITMAP_OPTIONS_NONE,
+            )?);
+        }
+        let backdrop = effects.backdrop.as_ref()?;
+
+        //有裁剪区域时不能复制画面，复制之后再恢复，
+        //Flush之后上一次混合的绘制已经完成，可以覆盖backdrop
+        let clips = self.suspend_clips();
+        context.Flush(ptr::null_mut(), ptr::null_mut());
+        let point = D2D1_POINT_2U { x: 0, y: 0 };
+        let target = context as *const ID2D1DeviceContext as *mut ID2D1RenderTarget;
+        let copied = SUCCEEDED(backdrop.CopyFromRenderTarget(&point, target, &rect));
+        self.resume_clips(clips);
+        if !copied {
+            return None;
+        }
+
+        //backdrop的原点是画面上的(rect.left, rect.top)，图片也移动到这个坐标系
+        let offset = Matrix3x2f::translation([-(rect.left as f32), -(rect.top as f32)]);
+        set_property(
+            &effects.affine,
+            D2D1_2DAFFINETRANSFORM_PROP_TRANSFORM_MATRIX,
+            &to_raw_matrix(&(transform * offset)),
+        )?;
+
+        //Blend特效的输入0是画面，输入1是图片
+        let mode = if blend == BlendMode::Multiply {
+            D2D1_BLEND_MODE_MULTIPLY
+        } else {
+            D2D1_BLEND_MODE_SCREEN
+        };
+        set_property(&effects.blend, D2D1_BLEND_PROP_MODE, &mode)?;
+        effects
+            .blend
+            .SetInput(0, backdrop.as_raw() as *const ID2D1Image, TRUE);
+        effects.blend.SetInput(1, &*source, TRUE);
+
+        self.target.set_transform(&Matrix3x2f::IDENTITY);
+        context.DrawImage(
+            &*effect_output(&effects.blend),
+            &D2D1_POINT_2F {
+                x: rect.left as f32,
+                y: rect.top as f32,
+            },
+            &D2D1_RECT_F {
+                left: 0.0,
+                top: 0.0,
+                right: width as f32,
+                bottom: height as f32,
+            },
+            D2D1_INTERPOLATION_MODE_NEAREST_NEIGHBOR,
+            D2D1_COMPOSITE_MODE_SOURCE_COPY,
+        );
+        Some(())
+    }
+
     fn check_text_format(&mut self, font_size: &u32) {
         if !self.text_formats.contains_key(&font_size) {
             let text_format = TextFormat::create(&self.dwfactory)
@@ -192,9 +1086,135 @@ impl D2DGraphics {
             self.text_formats.insert(*font_size, text_format);
         }
     }
+
+    /// 当前变换(包括窗口缩放)的平均缩放比例，用于决定曲线展开的精度
+    fn pixel_scale(&self) -> f64 {
+        let base = &self.base_transform;
+        let base_scale = ((base.a * base.d - base.b * base.c) as f64).abs().sqrt();
+        shape::transform_scale(&self.transform) * base_scale
+    }
+
+    /// 使用画刷填充多个多边形，纯色画刷按颜色缓存，渐变和图案画刷每次创建
+    fn fill_contours(&mut self, brush: &Brush, contours: &[Vec<[f64; 2]>], rule: FillRule) {
+        let fill_mode = match rule {
+            FillRule::NonZero => FillMode::Winding,
+            FillRule::EvenOdd => FillMode::Alternate,
+        };
+        let mut builder = match direct2d::geometry::Path::create(&self.factory) {
+            Ok(builder) => builder.fill_mode(fill_mode),
+            Err(_) => return,
+        };
+        for contour in contours {
+            let points: Vec<Point2f> = contour
+                .iter()
+                .map(|p| Point2f::new(p[0] as f32, p[1] as f32))
+                .collect();
+            builder = builder.with_line_figure(FigureBegin::Filled, FigureEnd::Closed, &points);
+        }
+        let path = match builder.finish() {
+            Ok(path) => path,
+            Err(_) => return,
+        };
+        match brush {
+            Brush::Solid(color) => {
+                self.check_brush(color);
+                self.target
+                    .fill_geometry(&path, self.solid_bursh.get(color).unwrap());
+            }
+            Brush::LinearGradient { start, end, stops } => {
+                //和canvas相同，起点和终点重合时不绘制
+                if stops.is_empty() || start == end {
+                    return;
+                }
+                if let Ok(brush) = LinearGradientBrush::create(&self.target)
+                    .with_start(Point2f::new(start[0] as f32, start[1] as f32))
+                    .with_end(Point2f::new(end[0] as f32, end[1] as f32))
+                    .with_stops(&gradient_stops(stops))
+                    .build()
+                {
+                    self.target.fill_geometry(&path, &brush);
+                }
+            }
+            Brush::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                if stops.is_empty() || *radius <= 0.0 {
+                    return;
+                }
+                if let Ok(brush) = RadialGradientBrush::create(&self.target)
+                    .with_center(Point2f::new(center[0] as f32, center[1] as f32))
+                    .with_radius(*radius as f32, *radius as f32)
+                    .with_stops(&gradient_stops(stops))
+                    .build()
+                {
+                    self.target.fill_geometry(&path, &brush);
+                }
+            }
+            Brush::Pattern { image, transform } => {
+                let bitmap = match image.native::<D2DImage>() {
+                    Some(image) => &image.bitmap,
+                    None => return,
+                };
+                if let Ok(brush) = BitmapBrush::create(&self.target)
+                    .with_bitmap(bitmap)
+                    .with_extend_mode_x(ExtendMode::Wrap)
+                    .with_extend_mode_y(ExtendMode::Wrap)
+                    .with_transform(to_matrix3x2f(transform))
+                    .build()
+                {
+                    self.target.fill_geometry(&path, &brush);
+                }
+            }
+        }
+    }
 }
 
-impl Graphics for D2DGraphics {
+impl D2DGraphics<BitmapTarget> {
+    /// 结束绘制并复制画布的位图，之后继续绘制
+    fn snapshot(&mut self) -> Option<Bitmap> {
+        //有裁剪区域时不能结束绘制
+        let clips = self.suspend_clips();
+        let _ = self.target.end_draw();
+        let bitmap = unsafe { self.copy_bitmap() };
+        self.target.begin_draw();
+        self.resume_clips(clips);
+        bitmap
+    }
+
+    /// 创建新的位图并复制绘图目标的位图，需要在end_draw之后调用
+    unsafe fn copy_bitmap(&self) -> Option<Bitmap> {
+        let mut source = ptr::null_mut();
+        if !SUCCEEDED(self.target.bitmap_target.GetBitmap(&mut source)) {
+            return None;
+        }
+        let source: ComPtr<ID2D1Bitmap> = ComPtr::from_raw(source);
+        let properties = D2D1_BITMAP_PROPERTIES {
+            pixelFormat: source.GetPixelFormat(),
+            dpiX: 96.0,
+            dpiY: 96.0,
+        };
+        let mut copy = ptr::null_mut();
+        if !SUCCEEDED(self.raw_target().CreateBitmap(
+            source.GetPixelSize(),
+            ptr::null(),
+            0,
+            &properties,
+            &mut copy,
+        )) {
+            return None;
+        }
+        let copy: ComPtr<ID2D1Bitmap> = ComPtr::from_raw(copy);
+        let point = D2D1_POINT_2U { x: 0, y: 0 };
+        if !SUCCEEDED(copy.CopyFromBitmap(&point, source.as_raw(), ptr::null())) {
+            return None;
+        }
+        Some(Bitmap::from_raw(copy.into_raw()))
+    }
+}
+
+impl<T: DerefMut<Target = RenderTarget>> Graphics for D2DGraphics<T> {
     fn fill_rect(&mut self, color: &[u8; 4], x: f64, y: f64, width: f64, height: f64) {
         self.check_brush(color);
         self.target.fill_rectangle(
@@ -203,25 +1223,28 @@ impl Graphics for D2DGraphics {
         );
     }
 
-    fn draw_image(
+    //染色和混合方式使用Direct2D 1.1的特效，不支持时在CPU上染色，混合方式按Normal处理
+    fn draw_image_with(
         &mut self,
-        transform: Option<Transform>,
+        transform: Option<Matrix2D>,
         image: &Image,
         src: Option<[f64; 4]>,
         dest: Option<[f64; 4]>,
+        options: &DrawOptions,
     ) {
+        let native = match image.native::<D2DImage>() {
+            Some(native) => native,
+            None => return,
+        };
         let (w, h) = (image.width(), image.height());
 
-        let dest_rect = if let Some(dest) = dest {
-            Rectf::new(
-                dest[0] as f32,
-                dest[1] as f32,
-                (dest[0] + dest[2]) as f32,
-                (dest[3] + dest[1]) as f32,
-            )
-        } else {
-            Rectf::new(0.0, 0.0, w as f32, h as f32)
-        };
+        let (transform, dest) = options.place(transform, dest.unwrap_or([0.0, 0.0, w, h]));
+        let dest_rect = Rectf::new(
+            dest[0] as f32,
+            dest[1] as f32,
+            (dest[0] + dest[2]) as f32,
+            (dest[3] + dest[1]) as f32,
+        );
 
         let src_rect = if let Some(src) = src {
             Rectf::new(
@@ -235,25 +1258,37 @@ impl Graphics for D2DGraphics {
         };
 
         let old_transform = self.target.transform();
-        let t = if let Some(transform) = transform {
-            Matrix3x2f::rotation(transform.rotate as f32, [0.5, 0.5])
-            *
-            //translation要先乘以之前的变比
-            old_transform * Matrix3x2f::translation([
-                transform.translate.0 as f32 * old_transform.a,
-                transform.translate.1 as f32 * old_transform.d,
-            ])
+        //先应用图片的变换，再应用窗口的缩放和平移
+        let t = if let Some(m) = transform {
+            to_matrix3x2f(&m) * old_transform
         } else {
             old_transform
         };
-        self.target.set_transform(&t);
-        self.target.draw_bitmap(
-            &image.bitmap,
-            dest_rect,
-            1.0,
-            BitmapInterpolationMode::Linear,
-            src_rect,
-        );
+        let sampling = options.sampling.unwrap_or(self.sampling);
+        let tint = [options.tint[0], options.tint[1], options.tint[2]];
+        let plain = tint == [255; 3] && options.blend == BlendMode::Normal;
+        if plain
+            || self
+                .draw_effect(&native.bitmap, options, src_rect, dest_rect, t, sampling)
+                .is_none()
+        {
+            let tinted = if plain {
+                None
+            } else {
+                native.tinted(&self.target, tint)
+            };
+            self.target.set_transform(&t);
+            self.target.draw_bitmap(
+                tinted.as_deref().unwrap_or(&native.bitmap),
+                dest_rect,
+                options.opacity() as f32,
+                match sampling {
+                    Sampling::Nearest => BitmapInterpolationMode::NearestNeighbor,
+                    Sampling::Linear => BitmapInterpolationMode::Linear,
+                },
+                src_rect,
+            );
+        }
         self.target.set_transform(&old_transform);
     }
 
@@ -273,11 +1308,150 @@ impl Graphics for D2DGraphics {
             DrawTextOptions::NONE,
         );
     }
+
+    fn fill_polygon(&mut self, color: &[u8; 4], points: &[[f64; 2]]) {
+        self.fill_contours(&Brush::Solid(*color), &[points.to_vec()], FillRule::NonZero);
+    }
+
+    //描边转换为多边形填充，和软件渲染的结果保持一致
+    fn stroke_polyline(
+        &mut self,
+        color: &[u8; 4],
+        points: &[[f64; 2]],
+        closed: bool,
+        style: &StrokeStyle,
+    ) {
+        let contours = shape::stroke_polygons(points, closed, style, self.pixel_scale());
+        self.fill_contours(&Brush::Solid(*color), &contours, FillRule::NonZero);
+    }
+
+    fn fill_path(&mut self, color: &[u8; 4], path: &Path, rule: FillRule) {
+        let contours: Vec<Vec<[f64; 2]>> = path
+            .flatten(self.pixel_scale())
+            .into_iter()
+            .map(|(points, _)| points)
+            .collect();
+        self.fill_contours(&Brush::Solid(*color), &contours, rule);
+    }
+
+    fn fill_polygon_with(&mut self, brush: &Brush, points: &[[f64; 2]]) {
+        self.fill_contours(brush, &[points.to_vec()], FillRule::NonZero);
+    }
+
+    fn fill_path_with(&mut self, brush: &Brush, path: &Path, rule: FillRule) {
+        let contours: Vec<Vec<[f64; 2]>> = path
+            .flatten(self.pixel_scale())
+            .into_iter()
+            .map(|(points, _)| points)
+            .collect();
+        self.fill_contours(brush, &contours, rule);
+    }
+
+    fn stroke_path(&mut self, color: &[u8; 4], path: &Path, style: &StrokeStyle) {
+        let scale = self.pixel_scale();
+        let mut contours = vec![];
+        for (points, closed) in path.flatten(scale) {
+            contours.extend(shape::stroke_polygons(&points, closed, style, scale));
+        }
+        self.fill_contours(&Brush::Solid(*color), &contours, FillRule::NonZero);
+    }
+
+    fn save(&mut self) {
+        self.stack.push(self.transform);
+    }
+
+    fn restore(&mut self) {
+        if let Some(transform) = self.stack.pop() {
+            self.set_transform(transform);
+        }
+    }
+
+    fn transform(&self) -> Matrix2D {
+        self.transform
+    }
+
+    fn set_transform(&mut self, transform: Matrix2D) {
+        self.transform = transform;
+        self.target
+            .set_transform(&(to_matrix3x2f(&transform) * self.base_transform));
+    }
+
+    //和坐标轴对齐时使用push_axis_aligned_clip，旋转或斜切之后使用图层的几何遮罩
+    fn push_clip(&mut self, rect: [f64; 4]) {
+        let [x, y, w, h] = rect;
+        let rect = [x as f32, y as f32, (x + w) as f32, (y + h) as f32];
+        let transform = self.target.transform();
+        let aligned = (transform.b == 0.0 && transform.c == 0.0)
+            || (transform.a == 0.0 && transform.d == 0.0);
+        let mask = if aligned {
+            None
+        } else {
+            self.create_layer_mask(rect)
+        };
+        let clip = Clip {
+            rect,
+            transform,
+            mask,
+        };
+        self.apply_clip(&clip);
+        self.clips.push(clip);
+    }
+
+    fn pop_clip(&mut self) {
+        if let Some(clip) = self.clips.pop() {
+            self.remove_clip(&clip);
+        }
+    }
+
+    fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+
+    fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    //复制游戏画面部分(物理像素)，不包括窗口上的黑边
+    fn capture_frame(&mut self) -> Option<ImageData> {
+        let size = unsafe { self.raw_target().GetPixelSize() };
+        let base = &self.base_transform;
+        let (width, height) = self.size;
+        let clamp = |v: f32, max: u32| (v.round().max(0.0) as u32).min(max);
+        let rect = D2D1_RECT_U {
+            left: clamp(base.x, size.width),
+            top: clamp(base.y, size.height),
+            right: clamp(base.x + width as f32 * base.a, size.width),
+            bottom: clamp(base.y + height as f32 * base.d, size.height),
+        };
+        //有裁剪区域时Direct2D不能复制绘图目标
+        let clips = self.suspend_clips();
+        let frame = self.read_pixels(rect);
+        self.resume_clips(clips);
+        frame
+    }
+}
+
+/// Windows后端，使用Direct2D绘图，rodio播放声音
+pub struct D2DBackend;
+
+impl Backend for D2DBackend {
+    type Image = D2DImage;
+    type Sound = Vec<u8>;
+    type Window = D2DWindow;
+    type Graphics = D2DGraphics;
+
+    fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+        run::<S>(title, width, height, settings);
+    }
+
+    fn play_sound(sound: &Vec<u8>) {
+        play_sound(sound);
+    }
 }
 
 /// 启动线程播放声音
-pub fn play_sound(sound: &Sound) {
-    let buffer = sound.buffer.clone();
+fn play_sound(sound: &[u8]) {
+    let buffer = sound.to_vec();
     thread::spawn(move || {
         let device = rodio::default_output_device();
         if device.is_none() {
@@ -295,7 +1469,7 @@ pub fn play_sound(sound: &Sound) {
     });
 }
 
-pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
+fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
     //第一次启动窗口不移动鼠标也会触发一次mouse move事件，过滤这个事件
     let initial_window_size = if let Some(size) = settings.window_size {
         [size.0, size.1]
@@ -305,55 +1479,51 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
 
     let mut events_loop = winit::EventsLoop::new();
 
-    let window = winit::WindowBuilder::new()
-        .with_dimensions(LogicalSize::new(
-            initial_window_size[0],
-            initial_window_size[1],
-        ))
-        .with_title(title)
-        .build(&events_loop)
-        .unwrap();
-
-    if let Some(path) = settings.icon_path {
-        let path = "./static/".to_owned() + path;
-        if let Ok(icon) = winit::Icon::from_path(path) {
-            window.set_window_icon(Some(icon));
-        }
-    }
+    let window = build_window(
+        title,
+        (initial_window_size[0], initial_window_size[1]),
+        &settings,
+        &events_loop,
+    );
 
     use winit::os::windows::WindowExt;
     let hwnd = window.get_hwnd() as HWND;
 
-    let d2d = direct2d::factory::Factory::new().unwrap();
+    //按照物理像素创建绘图目标，dpi固定为96，绘图单位即为像素
+    let mut hidpi_factor = window.get_hidpi_factor();
+    let mut logical_size = window.get_inner_size().unwrap_or_else(|| {
+        LogicalSize::new(initial_window_size[0], initial_window_size[1])
+    });
+    let physical_size = logical_size.to_physical(hidpi_factor);
+
+    //离屏画布在游戏线程上使用，工厂必须是多线程的
+    let d2d = create_factory();
     use direct2d::render_target::hwnd::HwndRenderTargetBuilder;
     let target = HwndRenderTargetBuilder::new(&d2d)
         .with_hwnd(hwnd)
         .with_usage(RenderTargetUsage::NONE)
         .with_target_type(RenderTargetType::Default)
-        .with_pixel_size(initial_window_size[0] as u32, initial_window_size[1] as u32)
+        .with_pixel_size(physical_size.width as u32, physical_size.height as u32)
         // .with_format(Format::R8G8B8A8Uint)
         // .with_alpha_mode(direct2d::enums::AlphaMode::Ignore)
         .with_present_options(PresentOptions::NONE)
-        // .with_dpi(20.0, 20.0)
+        .with_dpi(96.0, 96.0)
         .build()
         .unwrap();
 
-    let graphics = D2DGraphics {
-        solid_bursh: HashMap::new(),
-        text_formats: HashMap::new(),
+    let graphics = D2DGraphics::new(
         target,
-        dwfactory: directwrite::factory::Factory::new().unwrap(),
-    };
+        d2d,
+        directwrite::factory::Factory::new().unwrap(),
+        (width, height),
+        settings.sampling,
+    );
 
     let background_color = settings.background_color.unwrap_or([0, 0, 0, 255]);
-    //填充背景
-    let bg_brush = SolidColorBrush::create(&graphics.target)
-        .with_color(Color::new(
-            background_color[0] as f32,
-            background_color[1] as f32,
-            background_color[2] as f32,
-            background_color[3] as f32,
-        ))
+    let letterbox_color = settings.letterbox_color.unwrap_or(background_color);
+    //填充画面以外的部分
+    let letterbox_brush = SolidColorBrush::create(&graphics.target)
+        .with_color(to_color(&letterbox_color))
         .build()
         .unwrap();
 
@@ -368,16 +1538,25 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
         .build()
         .unwrap();
 
+    //离屏画布和窗口使用同一个设备
+    let (canvas_target, canvas_factory, canvas_dwfactory) = unsafe {
+        (
+            share(&*graphics.target),
+            share(&graphics.factory),
+            share(&graphics.dwfactory),
+        )
+    };
+
     let graphics = Arc::new(Mutex::new(graphics));
 
     let mut mouse_pos = [0.0; 2];
 
-    let draw_center = settings.draw_center;
-    let auto_scale = settings.auto_scale;
-    let mut scale_x = 1.0;
-    let mut scale_y = 1.0;
-    let mut trans_x = 0.0;
-    let mut trans_y = 0.0;
+    let mut viewport = Viewport::new(
+        settings.scale_mode,
+        (width, height),
+        (physical_size.width, physical_size.height),
+        hidpi_factor,
+    );
 
     let (thread_sender, main_receiver) = channel();
     let (main_sender, thread_receiver) = channel();
@@ -420,31 +1599,44 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
     let mut raw_rgba_images = vec![];
     let mut game_window = D2DWindow {
         new_size: None,
-        update_delay: Duration::from_micros(1000 * 1000 / settings.ups),
+        scale_factor: hidpi_factor,
+        timestep: FixedTimestep::new(settings.ups, settings.max_updates_per_frame),
         thread_sender: assets_sender,
+        commands: vec![],
+        quit: false,
+        sampling: settings.sampling,
+        target: canvas_target,
+        factory: canvas_factory,
+        dwfactory: canvas_dwfactory,
     };
     let mut game = S::new(&mut game_window);
     let update_timer = Instant::now();
-    let mut next_update_time = update_timer.elapsed();
     let mut next_log_time = update_timer.elapsed();
     let next_log_delay = Duration::from_millis(1000);
     let (mut fps, mut ups, mut ups_count, mut fps_count) = (0, 0, 0, 0);
     loop {
-        if update_timer.elapsed() >= next_update_time {
-            next_update_time = next_update_time + game_window.update_delay;
-            game.update(&mut game_window);
+        let updates = game_window
+            .timestep
+            .advance(update_timer.elapsed().as_secs_f64() * 1000.0);
+        for _ in 0..updates {
+            let time = game_window.timestep.step();
+            game.update(&mut game_window, time);
             ups_count += 1;
         }
+        for command in game_window.commands.drain(..) {
+            apply_command(&window, command);
+        }
+        if game_window.quit {
+            let _ = target_sender.send(("exit", Sizeu::new(0, 0)));
+            break;
+        }
         if let Ok((path, tp, data)) = assets_receiver.try_recv() {
             match data {
                 Ok(RawAssets::Blob(data)) => match tp {
                     AssetsType::Sound => game.on_assets_load(
                         &path,
                         tp,
-                        Ok(Assets::Sound(Sound {
-                            buffer: data,
-                            audio_type: AudioType::test(&path),
-                        })),
+                        Ok(Assets::Sound(Sound::new(AudioType::test(&path), data))),
                         &mut game_window,
                     ),
                     _ => game.on_assets_load(&path, tp, Ok(Assets::File(data)), &mut game_window),
@@ -471,22 +1663,22 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
             //加载图片资源
             if let Some((path, data)) = raw_rgba_images.pop() {
                 let (w, h) = (data.width(), data.height());
-                let buf = data.into_raw();
                 match Bitmap::create(&g.target)
                     .with_format(Format::R8G8B8A8Unorm)
-                    .with_raw_data(Sizeu::new(w, h), &buf, w * 4)
+                    .with_raw_data(Sizeu::new(w, h), &data, w * 4)
                     .build()
                 {
                     Ok(bmp) => {
-                        let sz = bmp.size();
+                        //不支持特效时保留像素，用于CPU染色
+                        let pixels = if g.supports_effects() {
+                            None
+                        } else {
+                            Some(data)
+                        };
                         game.on_assets_load(
                             &path,
                             AssetsType::Image,
-                            Ok(Assets::Image(Image {
-                                bitmap: Rc::new(bmp),
-                                width: sz.width as f64,
-                                height: sz.height as f64,
-                            })),
+                            Ok(Assets::Image(Image::new(D2DImage::new(bmp, pixels)))),
                             &mut game_window,
                         )
                     }
@@ -499,41 +1691,23 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
                 };
             }
 
-            let mut transform = Matrix3x2f::IDENTITY;
-            let (mut new_width, mut new_height) = (width, height);
-            if auto_scale {
-                //画面不超过窗口高度
-                new_height = graphics_size.height as f64;
-                new_width = (new_height / height) as f64 * width as f64;
-
-                if new_width > graphics_size.width as f64 {
-                    new_width = graphics_size.width as f64;
-                    new_height = (new_width / width) * height;
-                }
-                scale_x = new_width / width;
-                scale_y = new_height / height;
-                // println!("scalex={},scaley={}", scale_x, scale_y);
-                transform =
-                    transform * Matrix3x2f::scaling([scale_x as f32, scale_y as f32], [0.0, 0.0]);
-            } else {
-                scale_x = 1.0;
-                scale_y = 1.0;
-            }
-            if draw_center {
-                trans_x = (graphics_size.width as f64 - new_width) / 2.;
-                trans_y = (graphics_size.height as f64 - new_height) / 2.;
-                // println!("trans_x={},trans_y={}", trans_x, trans_y);
-                transform = transform * Matrix3x2f::translation([trans_x as f32, trans_y as f32]);
-            // transform = transform * Matrix3x2f::translation([(trans_x / scale_x) as f32, (trans_y / scale_y) as f32]);
-            } else {
-                trans_x = 0.0;
-                trans_y = 0.0;
-            }
-            g.target.set_transform(&transform);
-            let _ = game.draw(&mut *g, &mut game_window);
+            viewport = Viewport::new(
+                settings.scale_mode,
+                (width, height),
+                (graphics_size.width as f64, graphics_size.height as f64),
+                hidpi_factor,
+            );
+            let transform = Matrix3x2f::scaling(
+                [viewport.scale_x as f32, viewport.scale_y as f32],
+                [0.0, 0.0],
+            ) * Matrix3x2f::translation([viewport.x as f32, viewport.y as f32]);
+            g.reset_state(transform);
+            let time = game_window.timestep.frame_time();
+            let _ = game.draw(&mut *g, &mut game_window, time);
 
             //显示UPS/FPS
             if settings.show_ups_fps {
+                g.reset_state(transform);
                 g.target.draw_text(
                     &format!("UPS/FPS:{}/{}", ups, fps),
                     &fps_text_format,
@@ -542,34 +1716,18 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
                     DrawTextOptions::NONE,
                 );
             }
-            g.target.set_transform(&Matrix3x2f::IDENTITY);
+            g.reset_state(Matrix3x2f::IDENTITY);
 
-            //遮盖上部分窗口
-            g.target.fill_rectangle(
-                [0., 0., graphics_size.width as f32, trans_y as f32],
-                &bg_brush,
-            );
-            //遮盖下部分窗口
-            let (x, y, width, height) = (
-                0.,
-                (trans_y + height * scale_y) as f32,
-                graphics_size.width,
-                graphics_size.height - (trans_y + height * scale_y) as f32,
-            );
-            g.target
-                .fill_rectangle([x, y, x + width, y + height], &bg_brush);
-            //遮盖左部分窗口
-            g.target
-                .fill_rectangle([0.0, 0.0, trans_x as f32, graphics_size.height], &bg_brush);
-            //遮盖右部分窗口
-            let (x, y, width, height) = (
-                (trans_x + new_width) as f32,
-                0.0,
-                graphics_size.width - (trans_x + new_width) as f32,
-                graphics_size.height,
-            );
-            g.target
-                .fill_rectangle([x, y, x + width, y + height], &bg_brush);
+            //遮盖画面以外的部分
+            for [x, y, w, h] in viewport.letterbox((
+                graphics_size.width as f64,
+                graphics_size.height as f64,
+            )) {
+                g.target.fill_rectangle(
+                    [x as f32, y as f32, (x + w) as f32, (y + h) as f32],
+                    &letterbox_brush,
+                );
+            }
 
             let _ = main_sender.send("draw");
         }
@@ -582,124 +1740,76 @@ pub fn run<S: State>(title: &str, width: f64, height: f64, settings: Settings) {
         }
         events_loop.poll_events(|event| {
             match event {
-                winit::Event::WindowEvent {
-                    event: winit::WindowEvent::CursorMoved { position, .. },
+                winit::Event::DeviceEvent {
+                    event: winit::DeviceEvent::Key(input),
                     ..
                 } => {
-                    mouse_pos[0] = position.x;
-                    mouse_pos[1] = position.y;
-                    game.event(
-                        Event::MouseMove(
-                            (position.x - trans_x) / scale_x,
-                            (position.y - trans_y) / scale_y,
-                        ),
-                        &mut game_window,
-                    );
+                    if let Some(event) = key_event(&input) {
+                        game.event(event, &mut game_window);
+                    }
                     winit::ControlFlow::Continue
                 }
                 winit::Event::WindowEvent {
-                    event: winit::WindowEvent::MouseInput { state, button, .. },
+                    event: winit::WindowEvent::Resized(size),
                     ..
                 } => {
-                    match button {
-                        MouseButton::Left => {
-                            match state {
-                                ElementState::Released => game.event(
-                                    Event::Click(
-                                        (mouse_pos[0] - trans_x) / scale_x,
-                                        (mouse_pos[1] - trans_y) / scale_y,
-                                    ),
-                                    &mut game_window,
-                                ),
-                                _ => (),
-                            };
-                        }
-                        _ => (),
-                    };
+                    logical_size = size;
+                    let size = size.to_physical(hidpi_factor);
+                    game_window.new_size = Some((size.width, size.height));
+                    game.on_resize(logical_size.width, logical_size.height, &mut game_window);
                     winit::ControlFlow::Continue
                 }
-                winit::Event::DeviceEvent {
-                    event: winit::DeviceEvent::Key(input),
+                winit::Event::WindowEvent {
+                    event: winit::WindowEvent::HiDpiFactorChanged(factor),
                     ..
                 } => {
-                    if let Some(vk) = input.virtual_keycode {
-                        match vk {
-                            VirtualKeyCode::Key0
-                            | VirtualKeyCode::Key1
-                            | VirtualKeyCode::Key2
-                            | VirtualKeyCode::Key3
-                            | VirtualKeyCode::Key4
-                            | VirtualKeyCode::Key5
-                            | VirtualKeyCode::Key6
-                            | VirtualKeyCode::Key7
-                            | VirtualKeyCode::Key8
-                            | VirtualKeyCode::Key9
-                            | VirtualKeyCode::Numpad0
-                            | VirtualKeyCode::Numpad1
-                            | VirtualKeyCode::Numpad2
-                            | VirtualKeyCode::Numpad3
-                            | VirtualKeyCode::Numpad4
-                            | VirtualKeyCode::Numpad5
-                            | VirtualKeyCode::Numpad6
-                            | VirtualKeyCode::Numpad7
-                            | VirtualKeyCode::Numpad8
-                            | VirtualKeyCode::Numpad9 => {
-                                let key =
-                                    format!("{:?}", vk).replace("Key", "").replace("Numpad", "");
-                                match input.state {
-                                    ElementState::Pressed => {
-                                        game.event(Event::KeyDown(key), &mut game_window)
-                                    }
-                                    ElementState::Released => {
-                                        game.event(Event::KeyUp(key), &mut game_window)
-                                    }
-                                };
-                            }
-                            VirtualKeyCode::LControl | VirtualKeyCode::RControl => {
-                                match input.state {
-                                    ElementState::Pressed => game.event(
-                                        Event::KeyDown(String::from("CONTROL")),
-                                        &mut game_window,
-                                    ),
-                                    ElementState::Released => game.event(
-                                        Event::KeyUp(String::from("CONTROL")),
-                                        &mut game_window,
-                                    ),
-                                };
-                            }
-                            _ => {
-                                match input.state {
-                                    ElementState::Pressed => game.event(
-                                        Event::KeyDown(format!("{:?}", vk)),
-                                        &mut game_window,
-                                    ),
-                                    ElementState::Released => game
-                                        .event(Event::KeyUp(format!("{:?}", vk)), &mut game_window),
-                                };
-                            }
-                        };
-                    }
+                    hidpi_factor = factor;
+                    let size = logical_size.to_physical(hidpi_factor);
+                    game_window.new_size = Some((size.width, size.height));
+                    game_window.scale_factor = factor;
+                    game.event(Event::ScaleFactorChanged(factor), &mut game_window);
                     winit::ControlFlow::Continue
                 }
                 winit::Event::WindowEvent {
-                    event: winit::WindowEvent::Resized(size),
+                    event: winit::WindowEvent::CloseRequested,
                     ..
                 } => {
-                    game_window.new_size = Some((size.width, size.height));
-                    winit::ControlFlow::Continue
+                    if game.on_exit(&mut game_window) {
+                        let _ = target_sender.send(("exit", Sizeu::new(0, 0)));
+                        winit::ControlFlow::Break
+                    } else {
+                        winit::ControlFlow::Continue
+                    }
                 }
                 winit::Event::WindowEvent {
-                    event: winit::WindowEvent::CloseRequested,
+                    event: winit::WindowEvent::Focused(focused),
                     ..
                 } => {
-                    let _ = target_sender.send(("exit", Sizeu::new(0, 0)));
-                    winit::ControlFlow::Break
+                    game.on_focus_changed(focused, &mut game_window);
+                    winit::ControlFlow::Continue
+                }
+                winit::Event::Suspended(suspended) => {
+                    if suspended {
+                        game.on_suspend(&mut game_window);
+                    } else {
+                        game.on_resume(&mut game_window);
+                    }
+                    winit::ControlFlow::Continue
+                }
+                winit::Event::WindowEvent { event, .. } => {
+                    //鼠标位置是逻辑坐标，转换为物理像素之后再转换为游戏坐标
+                    if let Some(event) = mouse_event(&event, &mut mouse_pos, |x, y| {
+                        viewport.to_game(x * hidpi_factor, y * hidpi_factor)
+                    }) {
+                        game.event(event, &mut game_window);
+                    }
+                    winit::ControlFlow::Continue
                 }
                 _ => winit::ControlFlow::Continue,
             };
         });
 
-        if game_window.update_delay > Duration::from_millis(10) {
+        if game_window.timestep.dt() > 0.01 {
             thread::sleep(Duration::from_nanos(1));
         }
     }
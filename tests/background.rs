@@ -0,0 +1,174 @@
+#![cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+
+use image::{Rgba, RgbaImage};
+use mengine::engine::{BackgroundLayer, ScrollDir};
+use mengine::*;
+use std::io::Result;
+
+struct Scene {
+    layer: Option<BackgroundLayer>,
+}
+
+impl State for Scene {
+    fn new(_window: &mut impl Window) -> Self {
+        Scene { layer: None }
+    }
+
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {
+        if let Some(layer) = &mut self.layer {
+            layer.update();
+        }
+    }
+
+    fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {
+        if let Some(layer) = &self.layer {
+            layer.draw(g);
+        }
+    }
+
+    fn on_assets_load(&mut self, _: &str, _: AssetsType, _: Result<Assets>, _: &mut impl Window) {}
+}
+
+//16x12的图层，每个像素的颜色都不同
+fn pixel(x: u32, y: u32) -> [u8; 4] {
+    [x as u8 * 16, y as u8 * 20, ((x + y) * 8) as u8, 255]
+}
+
+fn bitmap() -> Image {
+    Image::new(RgbaImage::from_fn(16, 12, |x, y| Rgba(pixel(x, y))))
+}
+
+/// 视口10x8，画面比视口大一圈，超出视口的部分应该保持背景色
+///
+/// 视口左上角为(left, top)时，画面上的(x, y)对应图片上按宽高环绕之后的(x + left, y + top)
+fn assert_layer(layer: BackgroundLayer, updates: u32, left: i32, top: i32) {
+    let mut runner = run_headless::<Scene>(12., 10., Settings::default());
+    runner.state_mut().layer = Some(layer);
+    runner.step(updates);
+    let frame = runner.frame();
+    for y in 0..10 {
+        for x in 0..12 {
+            let expected = if x < 10 && y < 8 {
+                pixel(
+                    (x as i32 + left).rem_euclid(16) as u32,
+                    (y as i32 + top).rem_euclid(12) as u32,
+                )
+            } else {
+                [0, 0, 0, 255]
+            };
+            assert_eq!(frame.get_pixel(x, y).0, expected, "({}, {})", x, y);
+        }
+    }
+}
+
+/// 从图片中间的视口(3, 2)开始滚动，(left, top)为滚动之后视口的左上角
+fn assert_scroll(direction: ScrollDir, speed: f64, updates: u32, left: i32, top: i32) {
+    let viewport = Rect::new(3., 2., 13., 10.);
+    assert_layer(
+        BackgroundLayer::new(bitmap(), viewport, speed, direction),
+        updates,
+        left,
+        top,
+    );
+}
+
+/// 不滚动，直接绘制超出图片的视口
+fn assert_viewport(viewport: Rect) {
+    let (left, top) = (viewport.left as i32, viewport.top as i32);
+    assert_layer(
+        BackgroundLayer::new(bitmap(), viewport, 0., ScrollDir::Up),
+        0,
+        left,
+        top,
+    );
+}
+
+#[test]
+fn inside_image() {
+    assert_scroll(ScrollDir::Up, 0., 0, 3, 2);
+}
+
+#[test]
+fn scroll_up_wraps_bottom() {
+    assert_scroll(ScrollDir::Up, 3., 2, 3, 8);
+}
+
+//视口的上边超出图片之后回到0
+#[test]
+fn scroll_up_resets_viewport() {
+    assert_scroll(ScrollDir::Up, 3., 4, 3, 0);
+}
+
+#[test]
+fn scroll_down_wraps_top() {
+    assert_scroll(ScrollDir::Down, 3., 1, 3, -1);
+}
+
+//视口的下边超出图片之后回到图片底部
+#[test]
+fn scroll_down_resets_viewport() {
+    assert_scroll(ScrollDir::Down, 3., 4, 3, 4);
+}
+
+#[test]
+fn scroll_left_wraps_right() {
+    assert_scroll(ScrollDir::Left, 2., 2, 7, 2);
+}
+
+#[test]
+fn scroll_left_resets_viewport() {
+    assert_scroll(ScrollDir::Left, 2., 7, 0, 2);
+}
+
+#[test]
+fn scroll_right_wraps_left() {
+    assert_scroll(ScrollDir::Right, 2., 2, -1, 2);
+}
+
+#[test]
+fn scroll_right_resets_viewport() {
+    assert_scroll(ScrollDir::Right, 2., 7, 6, 2);
+}
+
+#[test]
+fn wraps_top_left() {
+    assert_viewport(Rect::new(-4., -3., 6., 5.));
+}
+
+#[test]
+fn wraps_top_right() {
+    assert_viewport(Rect::new(10., -3., 20., 5.));
+}
+
+#[test]
+fn wraps_bottom_left() {
+    assert_viewport(Rect::new(-4., 7., 6., 15.));
+}
+
+#[test]
+fn wraps_bottom_right() {
+    assert_viewport(Rect::new(10., 7., 20., 15.));
+}
+
+//视口比图片大时重复平铺
+#[test]
+fn tiles_viewport_larger_than_image() {
+    let mut runner = run_headless::<Scene>(40., 30., Settings::default());
+    runner.state_mut().layer = Some(BackgroundLayer::new(
+        bitmap(),
+        Rect::new(5., 4., 40., 30.),
+        0.,
+        ScrollDir::Up,
+    ));
+    runner.step(0);
+    let frame = runner.frame();
+    for y in 0..26 {
+        for x in 0..35 {
+            let expected = pixel((x + 5) % 16, (y + 4) % 12);
+            assert_eq!(frame.get_pixel(x, y).0, expected, "({}, {})", x, y);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+
+use mengine::*;
+use std::io::Result;
+
+struct Scene {
+    brush: Brush,
+}
+
+impl State for Scene {
+    fn new(_window: &mut impl Window) -> Self {
+        Scene {
+            brush: Brush::Solid([0, 0, 0, 0]),
+        }
+    }
+
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {}
+
+    //左半边用fill_rect_with，右半边用fill_polygon_with
+    fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {
+        g.fill_rect_with(&self.brush, 0., 0., 4., 4.);
+        g.fill_polygon_with(&self.brush, &[[4., 0.], [8., 0.], [8., 4.], [4., 4.]]);
+    }
+
+    fn on_assets_load(&mut self, _: &str, _: AssetsType, _: Result<Assets>, _: &mut impl Window) {}
+}
+
+fn render(brush: Brush) -> Runner<Scene> {
+    let mut runner = run_headless::<Scene>(8., 4., Settings::default());
+    runner.state_mut().brush = brush;
+    runner.step(0);
+    runner
+}
+
+#[test]
+fn translucent_solid_blends_like_polygon() {
+    let runner = render(Brush::Solid([255, 255, 0, 128]));
+    let frame = runner.frame();
+    let (rect, polygon) = (frame.get_pixel(1, 1).0, frame.get_pixel(6, 1).0);
+    assert_eq!(rect, polygon);
+    //半透明的黄色叠加在黑色背景上
+    for (actual, expected) in rect.iter().zip(&[128, 128, 0, 255]) {
+        assert!((*actual as i32 - expected).abs() <= 1, "{:?}", rect);
+    }
+}
+
+#[test]
+fn opaque_solid_covers_background() {
+    let runner = render(Brush::Solid([10, 20, 30, 255]));
+    assert_eq!(runner.frame().get_pixel(0, 0).0, [10, 20, 30, 255]);
+    assert_eq!(runner.frame().get_pixel(7, 3).0, [10, 20, 30, 255]);
+}
+
+#[test]
+fn transparent_solid_draws_nothing() {
+    let runner = render(Brush::Solid([255, 255, 255, 0]));
+    assert_eq!(runner.frame().get_pixel(2, 2).0, [0, 0, 0, 255]);
+    assert_eq!(runner.frame().get_pixel(5, 2).0, [0, 0, 0, 255]);
+}
@@ -0,0 +1,106 @@
+#![cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+
+use mengine::*;
+use std::io::Result;
+
+struct Counter {
+    updates: u32,
+    seed: f64,
+    allow_exit: bool,
+}
+
+impl State for Counter {
+    fn new(_window: &mut impl Window) -> Self {
+        Counter {
+            updates: 0,
+            seed: random(),
+            allow_exit: false,
+        }
+    }
+
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {
+        self.updates += 1;
+    }
+
+    fn draw(&mut self, _g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {}
+
+    fn on_assets_load(&mut self, _: &str, _: AssetsType, _: Result<Assets>, _: &mut impl Window) {}
+
+    fn on_exit(&mut self, _window: &mut impl Window) -> bool {
+        self.allow_exit
+    }
+}
+
+fn runner() -> Runner<Counter> {
+    run_headless::<Counter>(10., 10., Settings::default())
+}
+
+//虚拟时钟每次累加 1000/60 毫秒，有浮点误差
+fn assert_time(runner: &Runner<Counter>, ms: f64) {
+    assert!(
+        (runner.timestamp() - ms).abs() < 1e-6,
+        "{}",
+        runner.timestamp()
+    );
+}
+
+#[test]
+fn step_runs_one_update_per_tick() {
+    let mut runner = runner();
+    runner.step(60);
+    assert_eq!(runner.state().updates, 60);
+    assert_time(&runner, 1000.0);
+}
+
+#[test]
+fn pause_stops_updates() {
+    let mut runner = runner();
+    runner.step(10);
+    runner.window().pause();
+    runner.step(30);
+    assert_eq!(runner.state().updates, 10);
+    //暂停期间时钟仍然前进
+    assert_time(&runner, 40.0 * 1000.0 / 60.0);
+
+    runner.window().resume();
+    runner.step(10);
+    assert_eq!(runner.state().updates, 20);
+}
+
+#[test]
+fn time_scale_slows_updates() {
+    let mut runner = runner();
+    runner.window().set_time_scale(0.5);
+    runner.step(60);
+    assert_eq!(runner.state().updates, 30);
+}
+
+#[test]
+fn run_headless_resets_clock_and_rng() {
+    let mut first = runner();
+    first.window().set_time_scale(2.0);
+    first.step(30);
+    let seed = first.state().seed;
+
+    //不依赖第一个Runner的drop
+    let mut second = runner();
+    assert_time(&second, 0.0);
+    assert_eq!(second.state().seed, seed);
+    //上一个Runner修改的时间缩放也被重置
+    second.step(30);
+    assert_eq!(second.state().updates, 30);
+}
+
+#[test]
+fn close_respects_on_exit() {
+    let mut runner = runner();
+    assert!(!runner.close());
+    assert!(!runner.window().is_quit());
+
+    runner.state_mut().allow_exit = true;
+    assert!(runner.close());
+    assert!(runner.window().is_quit());
+}
@@ -0,0 +1,147 @@
+#![cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+
+use mengine::*;
+use std::fs::File;
+use std::io::{ErrorKind, Result};
+use std::path::PathBuf;
+
+struct Scene {
+    recorder: Recorder,
+    frame: u8,
+}
+
+impl State for Scene {
+    fn new(_window: &mut impl Window) -> Self {
+        Scene {
+            recorder: Recorder::new(25.),
+            frame: 0,
+        }
+    }
+
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {}
+
+    //每次绘制不同的颜色，最后采集画面
+    fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {
+        self.frame = self.frame.wrapping_add(1);
+        g.fill_rect(&[self.frame.wrapping_mul(40), 0, 255, 255], 0., 0., 2., 2.);
+        self.recorder.capture(g);
+    }
+
+    fn on_assets_load(&mut self, _: &str, _: AssetsType, _: Result<Assets>, _: &mut impl Window) {}
+}
+
+//每次更新20毫秒，25fps时每两次更新采集一帧，时间没有浮点误差
+fn runner(width: f64, height: f64) -> Runner<Scene> {
+    let settings = Settings {
+        ups: 50,
+        ..Settings::default()
+    };
+    run_headless::<Scene>(width, height, settings)
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mengine_recorder_{}.gif", name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn captures_at_recorder_rate() {
+    let mut runner = runner(4., 4.);
+    runner.state_mut().recorder.start();
+    runner.step(0);
+    for _ in 0..10 {
+        runner.step(1);
+    }
+    let timestamps = runner.state().recorder.timestamps();
+    assert_eq!(timestamps, &[0., 40., 80., 120., 160., 200.]);
+    assert_eq!(runner.state().recorder.frames().len(), 6);
+}
+
+#[test]
+fn does_not_backfill_when_behind() {
+    let mut runner = runner(4., 4.);
+    runner.state_mut().recorder.start();
+    runner.step(0);
+    runner.step(10);
+    runner.step(10);
+    assert_eq!(runner.state().recorder.timestamps(), &[0., 200., 400.]);
+}
+
+#[test]
+fn stopped_recorder_captures_nothing() {
+    let mut runner = runner(4., 4.);
+    runner.state_mut().recorder.start();
+    runner.step(0);
+    runner.state_mut().recorder.stop();
+    runner.step(4);
+    assert_eq!(runner.state().recorder.frames().len(), 1);
+}
+
+#[test]
+fn saves_gif_with_every_frame() {
+    let mut runner = runner(4., 3.);
+    runner.state_mut().recorder.start();
+    runner.step(0);
+    for _ in 0..3 {
+        runner.step(2);
+    }
+    let path = temp_path("frames");
+    runner.state().recorder.save_gif(&path).unwrap();
+
+    let mut reader = gif::Decoder::new(File::open(&path).unwrap())
+        .read_info()
+        .unwrap();
+    assert_eq!((reader.width(), reader.height()), (4, 3));
+    let mut delays = vec![];
+    while let Some(frame) = reader.read_next_frame().unwrap() {
+        assert_eq!((frame.width, frame.height), (4, 3));
+        delays.push(frame.delay);
+    }
+    //每帧间隔40毫秒，最后一帧使用采集间隔
+    assert_eq!(delays, vec![4, 4, 4, 4]);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn rejects_frames_of_different_size() {
+    let mut recorder = {
+        let mut runner = runner(4., 4.);
+        runner.state_mut().recorder.start();
+        runner.step(0);
+        std::mem::replace(&mut runner.state_mut().recorder, Recorder::new(25.))
+    };
+    //模拟录制中改变窗口大小
+    let mut runner = runner(6., 4.);
+    std::mem::swap(&mut runner.state_mut().recorder, &mut recorder);
+    runner.step(2);
+    let recorder = &runner.state().recorder;
+    assert_eq!(recorder.frames().len(), 2);
+
+    let path = temp_path("resized");
+    let err = recorder.save_gif(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!path.exists());
+}
+
+#[test]
+fn empty_recording_is_an_error() {
+    let path = temp_path("empty");
+    let err = Recorder::new(25.).save_gif(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+#[should_panic]
+fn zero_fps_panics() {
+    Recorder::new(0.);
+}
+
+#[test]
+#[should_panic]
+fn negative_fps_panics() {
+    Recorder::new(-30.);
+}
@@ -0,0 +1,172 @@
+#![cfg(all(
+    not(any(target_arch = "asmjs", target_arch = "wasm32")),
+    any(feature = "headless", not(windows))
+))]
+
+use image::{Rgba, RgbaImage};
+use mengine::engine::{Resource, Sprite};
+use mengine::*;
+use std::io::Result;
+
+struct Scene {
+    sprite: Option<Sprite>,
+    image: Option<(Image, [f64; 4], DrawOptions)>,
+}
+
+impl State for Scene {
+    fn new(_window: &mut impl Window) -> Self {
+        Scene {
+            sprite: None,
+            image: None,
+        }
+    }
+
+    fn update(&mut self, _window: &mut impl Window, _time: FrameTime) {}
+
+    fn draw(&mut self, g: &mut impl Graphics, _window: &mut impl Window, _time: FrameTime) {
+        if let Some(sprite) = &self.sprite {
+            sprite.draw(g);
+        }
+        if let Some((image, dest, options)) = &self.image {
+            g.draw_image_with(None, image, None, Some(*dest), options);
+        }
+    }
+
+    fn on_assets_load(&mut self, _: &str, _: AssetsType, _: Result<Assets>, _: &mut impl Window) {}
+}
+
+//4x2的图片，左右和上下都不对称
+fn pixel(x: u32, y: u32) -> [u8; 4] {
+    [x as u8 * 60, y as u8 * 200, 255 - x as u8 * 60, 255]
+}
+
+fn bitmap() -> Image {
+    Image::new(RgbaImage::from_fn(4, 2, |x, y| Rgba(pixel(x, y))))
+}
+
+fn animation() -> Animation {
+    Animation::new(bitmap(), vec![[0., 0., 4., 2.]], 10.)
+}
+
+fn render(scene: impl FnOnce(&mut Scene)) -> Runner<Scene> {
+    let mut runner = run_headless::<Scene>(8., 4., Settings::default());
+    scene(runner.state_mut());
+    runner.step(0);
+    runner
+}
+
+fn sprite(resource: Resource) -> Sprite {
+    Sprite::from_bitmap("sprite".to_string(), resource, Rect::new(0., 0., 8., 4.))
+}
+
+/// 图片绘制在(left, top)，flip_x/flip_y为期望的翻转方向
+fn assert_image(runner: &Runner<Scene>, left: u32, top: u32, flip_x: bool, flip_y: bool) {
+    let frame = runner.frame();
+    for y in 0..2 {
+        for x in 0..4 {
+            let sx = if flip_x { 3 - x } else { x };
+            let sy = if flip_y { 1 - y } else { y };
+            assert_eq!(
+                frame.get_pixel(left + x, top + y).0,
+                pixel(sx, sy),
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn static_sprite_facing_left_flips() {
+    let runner = render(|scene| {
+        let mut sprite = sprite(Resource::Static(bitmap()));
+        sprite.set_facing(Facing::Left);
+        scene.sprite = Some(sprite);
+    });
+    assert_image(&runner, 0, 0, true, false);
+}
+
+#[test]
+fn animated_sprite_facing_left_flips_once() {
+    let runner = render(|scene| {
+        let mut sprite = sprite(Resource::Animation(animation()));
+        sprite.set_facing(Facing::Left);
+        scene.sprite = Some(sprite);
+    });
+    assert_image(&runner, 0, 0, true, false);
+}
+
+#[test]
+fn sprite_takes_facing_from_animation() {
+    let mut anim = animation();
+    anim.set_facing(Facing::Left);
+    let sprite = sprite(Resource::Animation(anim));
+    assert_eq!(sprite.facing(), Facing::Left);
+    let runner = render(|scene| scene.sprite = Some(sprite));
+    assert_image(&runner, 0, 0, true, false);
+}
+
+#[test]
+fn sprite_and_animation_both_left_flip_once() {
+    let runner = render(|scene| {
+        let mut anim = animation();
+        anim.set_facing(Facing::Left);
+        let mut sprite = sprite(Resource::Animation(anim));
+        sprite.set_facing(Facing::Left);
+        scene.sprite = Some(sprite);
+    });
+    assert_image(&runner, 0, 0, true, false);
+}
+
+#[test]
+fn animated_sprite_facing_right_again_restores() {
+    let runner = render(|scene| {
+        let mut sprite = sprite(Resource::Animation(animation()));
+        sprite.set_facing(Facing::Left);
+        sprite.set_facing(Facing::Right);
+        scene.sprite = Some(sprite);
+    });
+    assert_image(&runner, 0, 0, false, false);
+}
+
+#[test]
+fn flip_y_mirrors_rows() {
+    let runner = render(|scene| {
+        let options = DrawOptions::new().with_flip(false, true);
+        scene.image = Some((bitmap(), [2., 1., 4., 2.], options));
+    });
+    assert_image(&runner, 2, 1, false, true);
+}
+
+#[test]
+fn flip_both_rotates_half_turn() {
+    let runner = render(|scene| {
+        let options = DrawOptions::new().with_flip(true, true);
+        scene.image = Some((bitmap(), [2., 1., 4., 2.], options));
+    });
+    assert_image(&runner, 2, 1, true, true);
+}
+
+#[test]
+fn anchor_moves_dest_and_flip_stays_in_place() {
+    //中心在(4, 2)，左上角移动到(2, 1)
+    let runner = render(|scene| {
+        let options = DrawOptions::new()
+            .with_anchor(0.5, 0.5)
+            .with_flip(true, false);
+        scene.image = Some((bitmap(), [4., 2., 4., 2.], options));
+    });
+    assert_image(&runner, 2, 1, true, false);
+    assert_eq!(runner.frame().get_pixel(1, 1).0, [0, 0, 0, 255]);
+    assert_eq!(runner.frame().get_pixel(6, 1).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn bottom_center_anchor() {
+    let runner = render(|scene| {
+        let options = DrawOptions::new().with_anchor(0.5, 1.0);
+        scene.image = Some((bitmap(), [4., 4., 4., 2.], options));
+    });
+    assert_image(&runner, 2, 2, false, false);
+}